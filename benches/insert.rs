@@ -0,0 +1,78 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use field_collex::Collexetable;
+use obj_alloc::{DefaultId, OrdIdMap};
+use span_core::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Obj(u32);
+impl Collexetable<u32> for Obj {
+    fn collexate(&self) -> u32 { self.0 }
+    
+    fn collexate_ref(&self) -> &u32 {
+        &self.0
+    }
+    
+    fn collexate_mut(&mut self) -> &mut u32 {
+        &mut self.0
+    }
+}
+
+const N: u32 = 10_000;
+
+fn empty() -> OrdIdMap<DefaultId, Obj, u32> {
+    OrdIdMap::new(Span::new_finite(0u32, N * 10), 10).unwrap()
+}
+
+fn filled() -> OrdIdMap<DefaultId, Obj, u32> {
+    let mut map = empty();
+    map.extend((0..N).map(|i| Obj(i * 10)));
+    map
+}
+
+fn bench_insert(c: &mut Criterion) {
+    c.bench_function("insert", |b| {
+        b.iter_batched_ref(empty, |map| {
+            for i in 0..N {
+                black_box(map.insert(Obj(i * 10)).unwrap());
+            }
+        }, BatchSize::LargeInput)
+    });
+    
+    // 全部冲突：失败路径不应触碰 id_map
+    c.bench_function("insert_conflicting", |b| {
+        b.iter_batched_ref(filled, |map| {
+            for i in 0..N {
+                black_box(map.insert(Obj(i * 10)).unwrap_err());
+            }
+        }, BatchSize::LargeInput)
+    });
+    
+    c.bench_function("insert_with_id_replace", |b| {
+        b.iter_batched_ref(filled, |map| {
+            for i in 0..N {
+                black_box(map.insert_with_id(DefaultId(i as u64 + 1), Obj(i * 10 + 1)).unwrap());
+            }
+        }, BatchSize::LargeInput)
+    });
+    
+    c.bench_function("extend", |b| {
+        b.iter_batched_ref(empty, |map| {
+            map.extend((0..N).map(|i| Obj(i * 10)));
+        }, BatchSize::LargeInput)
+    });
+    
+    // 逆序输入，排序后整批插入
+    c.bench_function("extend_sorted", |b| {
+        b.iter_batched(
+            || (empty(), (0..N).rev().map(|i| Obj(i * 10)).collect::<Vec<_>>()),
+            |(mut map, items)| {
+                black_box(map.extend_sorted(items).unwrap());
+                map
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, bench_insert);
+criterion_main!(benches);
@@ -0,0 +1,201 @@
+//! 单写者 actor：OrdIdMap 由专属线程独占，其余线程经通道发送命令，结果经一次性通道返回
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use field_collex::{Collexetable, FieldValue};
+use field_collex::collex::{InsertFieldCollexError, ModifyFieldCollexError};
+use crate::{ActorStopped, Id, OrdIdMap};
+
+type Query<K, E, V> = Box<dyn FnOnce(&OrdIdMap<K, E, V>) + Send>;
+type Modifier<E> = Box<dyn FnOnce(&mut E) + Send>;
+
+/// 发给 actor 的命令，每个命令自带回复通道
+enum Command<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    Insert(E, Sender<Result<K, InsertFieldCollexError<E>>>),
+    Remove(K, Sender<Option<E>>),
+    /// 失败时元素保持修改前的值
+    Modify(K, Modifier<E>, Sender<Result<(), ModifyFieldCollexError<()>>>),
+    Query(Query<K, E, V>),
+    Shutdown,
+}
+
+/// 持有 OrdIdMap 的 actor 线程
+pub struct OrdIdMapActor<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    handle: OrdIdMapHandle<K, E, V>,
+    thread: JoinHandle<OrdIdMap<K, E, V>>,
+}
+
+/// 向 actor 发送命令的句柄，可廉价克隆并在线程间传递
+pub struct OrdIdMapHandle<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    tx: Sender<Command<K, E, V>>,
+}
+
+impl<K, E, V> Clone for OrdIdMapHandle<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    fn clone(&self) -> Self {
+        Self { tx: self.tx.clone() }
+    }
+}
+
+impl<K, E, V> OrdIdMapActor<K, E, V>
+where
+    K: Id + Send + 'static,
+    E: Collexetable<V> + Send + 'static,
+    V: FieldValue + Send + 'static,
+{
+    /// 在新线程上启动 actor，按到达顺序逐个处理命令
+    pub fn spawn(map: OrdIdMap<K, E, V>) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let thread = thread::spawn(move || run(map, rx));
+        Self {
+            handle: OrdIdMapHandle { tx },
+            thread,
+        }
+    }
+    
+    pub fn handle(&self) -> OrdIdMapHandle<K, E, V> {
+        self.handle.clone()
+    }
+    
+    /// 处理完已排队的命令后停止 actor，取回 OrdIdMap；之后其他句柄的请求均返回 `ActorStopped`
+    pub fn shutdown(self) -> OrdIdMap<K, E, V> {
+        let _ = self.handle.tx.send(Command::Shutdown);
+        self.thread.join().expect("OrdIdMapActor thread panicked")
+    }
+}
+
+fn run<K, E, V>(mut map: OrdIdMap<K, E, V>, rx: Receiver<Command<K, E, V>>) -> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    // 请求方可能已放弃等待，回复失败可以忽略
+    for command in rx {
+        match command {
+            Command::Insert(elem, reply) => {
+                let _ = reply.send(map.insert(elem));
+            }
+            Command::Remove(id, reply) => {
+                let _ = reply.send(map.remove(id));
+            }
+            Command::Modify(id, f, reply) => {
+                let _ = reply.send(map.try_modify(id, f));
+            }
+            Command::Query(f) => f(&map),
+            Command::Shutdown => break,
+        }
+    }
+    map
+}
+
+impl<K, E, V> OrdIdMapHandle<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    /// 发送命令并等待回复
+    fn request<R>(&self, command: impl FnOnce(Sender<R>) -> Command<K, E, V>) -> Result<R, ActorStopped> {
+        let (reply, rx) = mpsc::channel();
+        self.tx.send(command(reply)).map_err(|_| ActorStopped)?;
+        rx.recv().map_err(|_| ActorStopped)
+    }
+    
+    pub fn insert(&self, elem: E) -> Result<Result<K, InsertFieldCollexError<E>>, ActorStopped> {
+        self.request(|reply| Command::Insert(elem, reply))
+    }
+    
+    pub fn remove(&self, id: K) -> Result<Option<E>, ActorStopped> {
+        self.request(|reply| Command::Remove(id, reply))
+    }
+    
+    pub fn modify<F>(&self, id: K, f: F) -> Result<Result<(), ModifyFieldCollexError<()>>, ActorStopped>
+    where
+        F: FnOnce(&mut E) + Send + 'static,
+    {
+        self.request(|reply| Command::Modify(id, Box::new(f), reply))
+    }
+    
+    /// 在 actor 线程上对 OrdIdMap 执行只读闭包，返回其结果
+    pub fn query<F, R>(&self, f: F) -> Result<R, ActorStopped>
+    where
+        F: FnOnce(&OrdIdMap<K, E, V>) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        self.request(|reply| Command::Query(Box::new(move |map| {
+            let _ = reply.send(f(map));
+        })))
+    }
+    
+    /// 按 Id 查询，返回元素的克隆
+    pub fn get_with_id(&self, id: K) -> Result<Option<E>, ActorStopped>
+    where
+        K: Send + 'static,
+        E: Clone + Send + 'static,
+    {
+        self.query(move |map| map.get_with_id(id).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use span_core::Span;
+    use crate::{ActorStopped, DefaultId, OrdIdMap};
+    use super::OrdIdMapActor;
+    use crate::test_util::TestO;
+    
+    #[test]
+    fn test_actor() {
+        let map: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::new(Span::new_finite(0u32, 1000u32), 10).unwrap();
+        let actor = OrdIdMapActor::spawn(map);
+        
+        let workers: Vec<_> = (0..4u32)
+            .map(|t| {
+                let handle = actor.handle();
+                thread::spawn(move || {
+                    for i in 0..10u32 {
+                        handle.insert(TestO((t * 10 + i) * 10)).unwrap().unwrap();
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        
+        let handle = actor.handle();
+        assert_eq!(handle.query(|map| map.len()).unwrap(), 40);
+        let id = handle.query(|map| map.id_at(0)).unwrap().unwrap();
+        handle.modify(id, |e| e.0 = 995).unwrap().unwrap();
+        assert_eq!(handle.get_with_id(id).unwrap(), Some(TestO(995)));
+        // 冲突时保持原值
+        assert!(handle.modify(id, |e| e.0 = 10).unwrap().is_err());
+        assert_eq!(handle.get_with_id(id).unwrap(), Some(TestO(995)));
+        assert!(handle.remove(id).unwrap().is_some());
+        
+        let map = actor.shutdown();
+        assert_eq!(map.len(), 39);
+        assert_eq!(handle.remove(id), Err(ActorStopped));
+    }
+}
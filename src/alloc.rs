@@ -0,0 +1,337 @@
+//! 把 OrdIdMap 当作区间分配器使用：按 unit 对齐的槽位查找空闲位置并分配
+
+use std::iter::successors;
+use std::ops::Range;
+use field_collex::{Collexetable, FieldValue};
+use span_core::Span;
+use field_collex::collex::InsertFieldCollexError;
+use crate::{AllocRunError, ChangeKind, CompactReport, Id, InsertAutoError, OrdIdMap};
+
+/// `insert_auto` 选择空闲槽位的策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlacementStrategy {
+    /// 第一个空闲槽位
+    #[default]
+    FirstFit,
+    /// 最小的空闲区间的第一个槽位，尽量保留大块空间
+    BestFit,
+    /// 从上次自动分配的位置之后继续查找，到末尾后从头开始
+    NextFit,
+}
+
+/// `[start, end)` 中从 `start` 起每隔 `width` 一个的格子起点
+///
+/// 只在下一个起点仍小于 `end` 时才前进，接近 V 的最大值时也不会溢出；`width` 须为正
+pub(crate) fn cell_starts<V: FieldValue>(start: V, end: V, width: V) -> impl Iterator<Item = V> {
+    successors((start < end).then_some(start), move |&s| (end - s > width).then(|| s + width))
+}
+
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    /// 有限 span 的起止值 `[start, end)`，无界 span 返回 None
+    pub(crate) fn finite_bounds(&self) -> Option<(V, V)> {
+        match self.collex.span() {
+            Span::Finite(range) => Some((range.start, range.end)),
+            _ => None,
+        }
+    }
+    
+    /// 从 span 起点按 unit 划分的槽位 `(起始值, 是否被占用)`，无界 span 返回 None
+    ///
+    /// 槽位 `[s, s + unit)` 中存在任一元素即视为被占用
+    pub(crate) fn slots(&self) -> Option<impl Iterator<Item = (V, bool)> + '_> {
+        self.cells(*self.collex.unit())
+    }
+    
+    /// 从 span 起点按 `width` 划分的格子 `(起始值, 是否被占用)`，无界 span 返回 None
+    ///
+    /// `width` 不为正时 panic
+    pub(crate) fn cells(&self, width: V) -> Option<impl Iterator<Item = (V, bool)> + '_> {
+        assert!(width > V::zero(), "cell width must be positive");
+        let (start, end) = self.finite_bounds()?;
+        let mut values = self.collex.iter().map(|p| p.collexate()).peekable();
+        // 未取出的值都不小于当前格子的起点，用 `v - s < width` 判断是否落入，避免计算 `s + width` 溢出
+        Some(
+            cell_starts(start, end, width).map(move |s| {
+                let occupied = values.peek().is_some_and(|&v| v - s < width);
+                while values.next_if(|&v| v - s < width).is_some() {}
+                (s, occupied)
+            })
+        )
+    }
+    
+    /// 连续空闲槽位合并而成的区间，按 field 顺序排列
+    fn gap_ranges(&self) -> Vec<Range<V>> {
+        let Some((_, end)) = self.finite_bounds() else {
+            return Vec::new();
+        };
+        let mut gaps = Vec::new();
+        let mut open = None;
+        for (s, occupied) in self.slots().into_iter().flatten() {
+            match (occupied, open) {
+                (true, Some(start)) => {
+                    gaps.push(start..s);
+                    open = None;
+                }
+                (false, None) => open = Some(s),
+                _ => {}
+            }
+        }
+        if let Some(start) = open {
+            gaps.push(start..end);
+        }
+        gaps
+    }
+    
+    /// 按 field 顺序遍历所有空闲区间（由连续的空闲槽位合并而成）
+    ///
+    /// 无界 span 无法划分槽位，不产出任何区间
+    pub fn gaps(&self) -> impl Iterator<Item = Span<V>> {
+        self.gap_ranges().into_iter().map(Span::Finite)
+    }
+    
+    /// 最大的空闲区间，长度相同时取靠前者
+    pub fn largest_gap(&self) -> Option<Span<V>> {
+        self.gap_ranges()
+            .into_iter()
+            .fold(None, |best: Option<Range<V>>, g| match best {
+                Some(b) if b.end - b.start >= g.end - g.start => Some(b),
+                _ => Some(g),
+            })
+            .map(Span::Finite)
+    }
+    
+    /// 空闲区间的总长度
+    pub fn free_space(&self) -> V {
+        self.gap_ranges()
+            .into_iter()
+            .fold(V::zero(), |acc, g| acc + (g.end - g.start))
+    }
+    
+    /// 按策略选出一个空闲槽位，由 `make(槽位起始值)` 构造元素并插入
+    pub fn insert_auto<F>(&mut self, make: F, strategy: PlacementStrategy) -> Result<K, InsertAutoError<E>>
+    where
+        F: FnOnce(V) -> E,
+    {
+        let slot = match strategy {
+            PlacementStrategy::FirstFit => self
+                .slots()
+                .ok_or(InsertAutoError::Unbounded)?
+                .find(|&(_, occupied)| !occupied)
+                .map(|(s, _)| s),
+            PlacementStrategy::BestFit => {
+                if self.finite_bounds().is_none() {
+                    return Err(InsertAutoError::Unbounded);
+                }
+                self.gap_ranges()
+                    .into_iter()
+                    .fold(None, |best: Option<Range<V>>, g| match best {
+                        Some(b) if b.end - b.start <= g.end - g.start => Some(b),
+                        _ => Some(g),
+                    })
+                    .map(|g| g.start)
+            }
+            PlacementStrategy::NextFit => {
+                let from = self.next_fit;
+                let mut free = self
+                    .slots()
+                    .ok_or(InsertAutoError::Unbounded)?
+                    .filter(|&(_, occupied)| !occupied)
+                    .map(|(s, _)| s);
+                let first = free.next();
+                match from {
+                    Some(from) if first.is_some_and(|s| s < from) => {
+                        free.find(|&s| s >= from).or(first)
+                    }
+                    _ => first,
+                }
+            }
+        };
+        let slot = slot.ok_or(InsertAutoError::NoRoom)?;
+        
+        let id = self.insert(make(slot)).map_err(|err| match err {
+            InsertFieldCollexError::OutOfSpan(e) => InsertAutoError::OutOfSpan(e),
+            InsertFieldCollexError::AlreadyExist(e) => InsertAutoError::AlreadyExist(e),
+        })?;
+        if strategy == PlacementStrategy::NextFit {
+            self.next_fit = Some(slot + *self.collex.unit());
+        }
+        Ok(id)
+    }
+    
+    /// 整理碎片：按 field 顺序把元素依次挪到从 span 起点开始、按 unit 对齐的槽位上，Id 保持不变
+    ///
+    /// 元素只会向前移动；返回每个被移动元素的新旧 field 值，便于修正外部按位置的引用。
+    /// 无界 span 不做任何改动
+    pub fn compact(&mut self) -> CompactReport<K, V> {
+        let mut report = CompactReport { moved: Vec::new() };
+        let Some((start, _)) = self.finite_bounds() else {
+            return report;
+        };
+        let unit = *self.collex.unit();
+        let values: Vec<V> = self.collex.iter().map(|p| p.collexate()).collect();
+        
+        let mut next = start;
+        for old in values {
+            let new = if next <= old { next } else { old };
+            next = new + unit;
+            if new == old {
+                continue;
+            }
+            let Ok(mut pair) = self.collex.remove(old) else { continue };
+            *pair.collexate_mut() = new;
+            let id = pair.0;
+            match self.collex.insert(pair) {
+                Ok(_) => {
+                    self.set_index(id, new);
+                    self.changes.record(id, ChangeKind::Modified);
+                    report.moved.push((id, old, new));
+                }
+                Err(InsertFieldCollexError::OutOfSpan(mut pair) | InsertFieldCollexError::AlreadyExist(mut pair)) => {
+                    // 原位置刚刚腾出，放回必然成功
+                    *pair.collexate_mut() = old;
+                    let _ = self.collex.insert(pair);
+                    next = old + unit;
+                }
+            }
+        }
+        report
+    }
+    
+    /// 分配 `count` 个相邻的空闲槽位，并由 `make(序号, 槽位起始值)` 构造元素后一次性插入
+    ///
+    /// 元素的 field 值必须落在给定槽位内；任一元素放不进去时已插入的元素全部撤回
+    pub fn alloc_run<F>(&mut self, count: usize, mut make: F) -> Result<Vec<K>, AllocRunError<V>>
+    where
+        F: FnMut(usize, V) -> E,
+    {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let unit = *self.collex.unit();
+        let mut run = Vec::with_capacity(count);
+        for (s, occupied) in self.slots().ok_or(AllocRunError::Unbounded)? {
+            if occupied {
+                run.clear();
+                continue;
+            }
+            run.push(s);
+            if run.len() == count {
+                break;
+            }
+        }
+        if run.len() < count {
+            return Err(AllocRunError::NoRoom(count));
+        }
+        
+        let elems: Vec<E> = run
+            .iter()
+            .enumerate()
+            .map(|(i, &slot)| make(i, slot))
+            .collect();
+        for (index, (e, &slot)) in elems.iter().zip(&run).enumerate() {
+            let value = e.collexate();
+            if value < slot || value >= slot + unit {
+                return Err(AllocRunError::Misplaced { index, slot, value });
+            }
+        }
+        
+        let mut ids = Vec::with_capacity(count);
+        for (index, (e, slot)) in elems.into_iter().zip(run).enumerate() {
+            let value = e.collexate();
+            match self.insert(e) {
+                Ok(id) => ids.push(id),
+                Err(_) => {
+                    for id in ids {
+                        self.remove(id);
+                    }
+                    return Err(AllocRunError::Misplaced { index, slot, value });
+                }
+            }
+        }
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use span_core::Span;
+    use crate::{AllocRunError, DefaultId, OrdIdMap, PlacementStrategy};
+    use crate::test_util::{TestO, new_map};
+    
+    #[test]
+    fn test_alloc_run() {
+        let mut map = new_map();
+        map.insert(TestO(10)).unwrap();
+        map.insert(TestO(40)).unwrap();
+        
+        // 槽位 20、30 之后被 40 打断，首个连续三格从 50 开始
+        let ids = map.alloc_run(3, |_, v| TestO(v)).unwrap();
+        let values: Vec<u32> = ids.iter().map(|&id| map.id_map[id]).collect();
+        assert_eq!(values, vec![50, 60, 70]);
+        
+        assert_eq!(map.alloc_run(3, |_, v| TestO(v)), Err(AllocRunError::NoRoom(3)));
+        assert_eq!(
+            map.alloc_run(1, |_, _| TestO(95)),
+            Err(AllocRunError::Misplaced { index: 0, slot: 0, value: 95 })
+        );
+        assert_eq!(map.len(), 5);
+    }
+    
+    #[test]
+    fn test_gaps() {
+        let mut map = new_map();
+        map.insert(TestO(10)).unwrap();
+        map.insert(TestO(45)).unwrap();
+        
+        let gaps: Vec<_> = map.gaps().collect();
+        assert_eq!(gaps, vec![
+            Span::Finite(0u32..10),
+            Span::Finite(20..40),
+            Span::Finite(50..100),
+        ]);
+        assert_eq!(map.largest_gap(), Some(Span::Finite(50..100)));
+        assert_eq!(map.free_space(), 80);
+    }
+    
+    #[test]
+    fn test_insert_auto() {
+        let mut map = new_map();
+        map.insert(TestO(20)).unwrap();
+        map.insert(TestO(50)).unwrap();
+        
+        let at = |map: &OrdIdMap<DefaultId, TestO, u32>, id| map.id_map[id];
+        let first = map.insert_auto(TestO, PlacementStrategy::FirstFit).unwrap();
+        assert_eq!(at(&map, first), 0);
+        // 空闲区间 [10, 20)、[30, 50)、[60, 100) 中最小的是 [10, 20)
+        let best = map.insert_auto(TestO, PlacementStrategy::BestFit).unwrap();
+        assert_eq!(at(&map, best), 10);
+        
+        let next = map.insert_auto(TestO, PlacementStrategy::NextFit).unwrap();
+        assert_eq!(at(&map, next), 30);
+        let next = map.insert_auto(TestO, PlacementStrategy::NextFit).unwrap();
+        assert_eq!(at(&map, next), 40);
+        let next = map.insert_auto(TestO, PlacementStrategy::NextFit).unwrap();
+        assert_eq!(at(&map, next), 60);
+    }
+    
+    #[test]
+    fn test_compact() {
+        let mut map = new_map();
+        let a = map.insert(TestO(0)).unwrap();
+        let b = map.insert(TestO(30)).unwrap();
+        let c = map.insert(TestO(70)).unwrap();
+        
+        let report = map.compact();
+        assert_eq!(report.moved, vec![(b, 30, 10), (c, 70, 20)]);
+        assert_eq!(map.get_with_id(a), Some(&TestO(0)));
+        assert_eq!(map.get_with_id(b), Some(&TestO(10)));
+        assert_eq!(map.id_map[c], 20);
+        assert_eq!(map.largest_gap(), Some(Span::Finite(30..100)));
+        assert!(map.validate().is_ok());
+    }
+}
@@ -0,0 +1,266 @@
+//! 以大块连续内存存放元素的 OrdIdMap：collex 中只存轻量的句柄，元素本身按块分配
+//!
+//! 适合元素内含 Box/Vec 等、批量插入时分配器压力大的场景；删除后的槽位会被复用
+
+use field_collex::{Collexetable, FieldValue};
+use field_collex::collex::{InsertFieldCollexError, NewFieldCollexError};
+use span_core::Span;
+use crate::{Id, OrdIdMap, ReplaceError};
+
+/// 按块分配的元素存储，槽位下标在元素存活期间保持不变
+#[derive(Debug, Clone)]
+pub struct Arena<E> {
+    chunks: Vec<Vec<Option<E>>>,
+    chunk_size: usize,
+    /// 已使用过的槽位数（含已释放的）
+    tail: usize,
+    free: Vec<usize>,
+    len: usize,
+}
+
+impl<E> Arena<E> {
+    /// 每块容纳 `chunk_size` 个元素（至少为 1）
+    pub fn new(chunk_size: usize) -> Self {
+        Self {
+            chunks: Vec::new(),
+            chunk_size: chunk_size.max(1),
+            tail: 0,
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+    
+    /// 预先分配足够的块，使之后 `additional` 次 `alloc` 不再申请内存
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self.tail + additional.saturating_sub(self.free.len());
+        while self.chunks.len() * self.chunk_size < needed {
+            self.chunks.push(Vec::with_capacity(self.chunk_size));
+        }
+    }
+    
+    /// 放入元素，返回其槽位下标
+    pub fn alloc(&mut self, elem: E) -> usize {
+        self.len += 1;
+        if let Some(index) = self.free.pop() {
+            self.chunks[index / self.chunk_size][index % self.chunk_size] = Some(elem);
+            return index;
+        }
+        let index = self.tail;
+        if index / self.chunk_size == self.chunks.len() {
+            self.chunks.push(Vec::with_capacity(self.chunk_size));
+        }
+        self.chunks[index / self.chunk_size].push(Some(elem));
+        self.tail += 1;
+        index
+    }
+    
+    pub fn get(&self, index: usize) -> Option<&E> {
+        self.chunks.get(index / self.chunk_size)?.get(index % self.chunk_size)?.as_ref()
+    }
+    
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut E> {
+        self.chunks.get_mut(index / self.chunk_size)?.get_mut(index % self.chunk_size)?.as_mut()
+    }
+    
+    /// 取出元素并释放槽位
+    pub fn take(&mut self, index: usize) -> Option<E> {
+        let elem = self.chunks.get_mut(index / self.chunk_size)?.get_mut(index % self.chunk_size)?.take()?;
+        self.free.push(index);
+        self.len -= 1;
+        Some(elem)
+    }
+    
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    
+    /// 已分配的块数
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+}
+
+/// collex 中存放的句柄：元素的 field 值与其在 Arena 中的槽位
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArenaHandle<V> {
+    value: V,
+    index: usize,
+}
+
+impl<V> ArenaHandle<V> {
+    /// 元素在 Arena 中的槽位
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<V: FieldValue> Collexetable<V> for ArenaHandle<V> {
+    fn collexate(&self) -> V { self.value }
+    
+    fn collexate_ref(&self) -> &V {
+        &self.value
+    }
+    
+    fn collexate_mut(&mut self) -> &mut V {
+        &mut self.value
+    }
+}
+
+/// 元素存放在 Arena 中的 OrdIdMap
+///
+/// 按 field 值的排序与按 Id 的查找都只涉及句柄；元素按块连续存放，批量插入时只在块满时申请内存
+#[derive(Debug)]
+pub struct ArenaOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    map: OrdIdMap<K, ArenaHandle<V>, V>,
+    arena: Arena<E>,
+}
+
+impl<K, E, V> ArenaOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    /// Arena 每块容纳 `chunk_size` 个元素
+    pub fn new(span: Span<V>, unit: V, chunk_size: usize) -> Result<Self, NewFieldCollexError<V>> {
+        Ok(Self {
+            map: OrdIdMap::new(span, unit)?,
+            arena: Arena::new(chunk_size),
+        })
+    }
+    
+    pub fn insert(&mut self, elem: E) -> Result<K, InsertFieldCollexError<E>> {
+        let value = elem.collexate();
+        let index = self.arena.alloc(elem);
+        self.map.insert(ArenaHandle { value, index }).map_err(|err| match err {
+            InsertFieldCollexError::OutOfSpan(h) => InsertFieldCollexError::OutOfSpan(self.release(h)),
+            InsertFieldCollexError::AlreadyExist(h) => InsertFieldCollexError::AlreadyExist(self.release(h)),
+        })
+    }
+    
+    /// 批量插入，先为所有元素预留 Arena 与 id_map 的空间；无法放入的元素被丢弃
+    pub fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = E>,
+    {
+        let iter = iter.into_iter();
+        let additional = iter.size_hint().0;
+        self.arena.reserve(additional);
+        self.map.reserve(additional);
+        for elem in iter {
+            let _ = self.insert(elem);
+        }
+    }
+    
+    pub fn remove(&mut self, id: K) -> Option<E> {
+        let handle = self.map.remove(id)?;
+        Some(self.release(handle))
+    }
+    
+    /// 以新元素替换 Id 对应的元素，返回旧元素；失败时旧元素保持原样
+    pub fn replace(&mut self, id: K, new: E) -> Result<E, ReplaceError<E>> {
+        let Some(index) = self.map.get_with_id(id).map(ArenaHandle::index) else {
+            return Err(ReplaceError::CannotFind(new));
+        };
+        let handle = ArenaHandle { value: new.collexate(), index };
+        match self.map.replace(id, handle) {
+            Ok(_) => {
+                let slot = self.arena.get_mut(index).expect("arena slot of a live handle");
+                Ok(std::mem::replace(slot, new))
+            }
+            Err(ReplaceError::CannotFind(_)) => Err(ReplaceError::CannotFind(new)),
+            Err(ReplaceError::OutOfSpan(_)) => Err(ReplaceError::OutOfSpan(new)),
+            Err(ReplaceError::AlreadyExist(_)) => Err(ReplaceError::AlreadyExist(new)),
+        }
+    }
+    
+    pub fn get_with_id(&self, id: K) -> Option<&E> {
+        let handle = self.map.get_with_id(id)?;
+        self.arena.get(handle.index)
+    }
+    
+    pub fn id_at(&self, value: V) -> Option<K> {
+        self.map.id_at(value)
+    }
+    
+    pub fn contains_id(&self, id: K) -> bool {
+        self.map.contains_id(id)
+    }
+    
+    /// 按 field 值升序遍历
+    pub fn iter(&self) -> impl Iterator<Item = (K, &E)> {
+        self.map
+            .iter()
+            .map(|(id, handle)| (id, self.arena.get(handle.index).expect("arena slot of a live handle")))
+    }
+    
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+    
+    pub fn arena(&self) -> &Arena<E> {
+        &self.arena
+    }
+    
+    /// 释放句柄对应的槽位，取回元素
+    fn release(&mut self, handle: ArenaHandle<V>) -> E {
+        self.arena.take(handle.index).expect("arena slot of a live handle")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use field_collex::Collexetable;
+    use field_collex::collex::InsertFieldCollexError;
+    use span_core::Span;
+    use crate::DefaultId;
+    use super::ArenaOrdIdMap;
+    
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestO(u32, Box<str>);
+    impl Collexetable<u32> for TestO {
+        fn collexate(&self) -> u32 { self.0 }
+        
+        fn collexate_ref(&self) -> &u32 {
+            &self.0
+        }
+        
+        fn collexate_mut(&mut self) -> &mut u32 {
+            &mut self.0
+        }
+    }
+    
+    #[test]
+    fn test_arena_map() {
+        let mut map: ArenaOrdIdMap<DefaultId, TestO, u32> =
+            ArenaOrdIdMap::new(Span::new_finite(0u32, 100u32), 10, 4).unwrap();
+        map.extend((0..6u32).map(|i| TestO(i * 10, i.to_string().into())));
+        assert_eq!(map.len(), 6);
+        assert_eq!(map.arena().chunk_count(), 2);
+        
+        let a = map.id_at(20).unwrap();
+        assert_eq!(map.remove(a), Some(TestO(20, "2".into())));
+        assert!(matches!(map.insert(TestO(10, "x".into())), Err(InsertFieldCollexError::AlreadyExist(_))));
+        // 空出的槽位被复用
+        let b = map.insert(TestO(25, "b".into())).unwrap();
+        assert_eq!(map.arena().len(), 6);
+        assert_eq!(map.arena().chunk_count(), 2);
+        
+        assert_eq!(map.replace(b, TestO(95, "c".into())), Ok(TestO(25, "b".into())));
+        assert_eq!(map.get_with_id(b), Some(&TestO(95, "c".into())));
+        assert_eq!(map.iter().map(|(_, e)| e.0).collect::<Vec<_>>(), vec![0, 10, 30, 40, 50, 95]);
+    }
+}
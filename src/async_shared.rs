@@ -0,0 +1,203 @@
+//! 异步共享：tokio::sync::RwLock 封装的 OrdIdMap，等待锁时让出执行器；另有异步的保存与加载
+
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use field_collex::{Collexetable, FieldValue};
+use field_collex::collex::{InsertFieldCollexError, ModifyFieldCollexError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use crate::persist::temp_path;
+#[cfg(unix)]
+use crate::persist::parent_dir;
+use crate::{Id, OrdIdMap, PersistError};
+
+/// 可廉价克隆的异步共享 OrdIdMap 句柄，所有克隆指向同一份数据
+#[derive(Debug)]
+pub struct AsyncOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    inner: Arc<RwLock<OrdIdMap<K, E, V>>>,
+}
+
+impl<K, E, V> Clone for AsyncOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<K, E, V> From<OrdIdMap<K, E, V>> for AsyncOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    fn from(map: OrdIdMap<K, E, V>) -> Self {
+        Self::new(map)
+    }
+}
+
+impl<K, E, V> AsyncOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    pub fn new(map: OrdIdMap<K, E, V>) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(map)),
+        }
+    }
+    
+    /// 持读锁访问整个 OrdIdMap
+    pub async fn read(&self) -> RwLockReadGuard<'_, OrdIdMap<K, E, V>> {
+        self.inner.read().await
+    }
+    
+    /// 持写锁访问整个 OrdIdMap
+    pub async fn write(&self) -> RwLockWriteGuard<'_, OrdIdMap<K, E, V>> {
+        self.inner.write().await
+    }
+    
+    pub async fn insert(&self, elem: E) -> Result<K, InsertFieldCollexError<E>> {
+        self.write().await.insert(elem)
+    }
+    
+    pub async fn insert_with_id(&self, id: K, elem: E) -> Result<Option<E>, InsertFieldCollexError<E>> {
+        self.write().await.insert_with_id(id, elem)
+    }
+    
+    pub async fn remove(&self, id: K) -> Option<E> {
+        self.write().await.remove(id)
+    }
+    
+    pub async fn modify<F, R>(&self, id: K, f: F) -> Result<R, ModifyFieldCollexError<(R, E)>>
+    where
+        F: FnOnce(&mut E) -> R,
+    {
+        self.write().await.modify(id, f)
+    }
+    
+    /// 按 Id 查询，返回元素的克隆
+    pub async fn get_with_id(&self, id: K) -> Option<E>
+    where
+        E: Clone,
+    {
+        self.read().await.get_with_id(id).cloned()
+    }
+    
+    /// 按 Id 查询，在读锁内对元素执行闭包
+    pub async fn with<F, R>(&self, id: K, f: F) -> Option<R>
+    where
+        F: FnOnce(&E) -> R,
+    {
+        self.read().await.get_with_id(id).map(f)
+    }
+    
+    pub async fn len(&self) -> usize {
+        self.read().await.len()
+    }
+    
+    pub async fn is_empty(&self) -> bool {
+        self.read().await.is_empty()
+    }
+    
+    /// 以 `save_checked` 的格式异步保存
+    ///
+    /// 只在序列化期间持读锁，写文件时不阻塞其他读写；中途失败时原文件保持不变
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<(), PersistError>
+    where
+        K: Serialize,
+        E: Serialize,
+        V: Serialize,
+    {
+        let bytes = self.read().await.to_checked_bytes()?;
+        let path = path.as_ref();
+        let tmp = temp_path(path);
+        let result: Result<(), PersistError> = async {
+            let mut file = tokio::fs::File::create(&tmp).await?;
+            file.write_all(&bytes).await?;
+            file.sync_all().await?;
+            tokio::fs::rename(&tmp, path).await?;
+            #[cfg(unix)]
+            tokio::fs::File::open(parent_dir(path)).await?.sync_all().await?;
+            Ok(())
+        }.await;
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(&tmp).await;
+        }
+        result
+    }
+    
+    /// 异步加载 `save`（或 `save_checked`）保存的文件
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self, PersistError>
+    where
+        K: DeserializeOwned,
+        E: DeserializeOwned,
+        V: DeserializeOwned + fmt::Debug,
+    {
+        let bytes = tokio::fs::read(path).await?;
+        OrdIdMap::from_checked_bytes(&bytes).map(Self::new)
+    }
+    
+    /// 若当前句柄是唯一的，取回内部的 OrdIdMap；否则原样返回句柄
+    pub fn try_unwrap(self) -> Result<OrdIdMap<K, E, V>, Self> {
+        Arc::try_unwrap(self.inner)
+            .map(RwLock::into_inner)
+            .map_err(|inner| Self { inner })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use field_collex::collex::serialize::{default_span, default_unit};
+    use crate::{DefaultId, OrdIdMap};
+    use super::AsyncOrdIdMap;
+    use crate::test_util::TestO;
+    
+    #[tokio::test]
+    async fn test_async_shared_save_load() {
+        let map: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::new(default_span(), default_unit()).unwrap();
+        let shared = AsyncOrdIdMap::new(map);
+        
+        let tasks: Vec<_> = (0..4u32)
+            .map(|t| {
+                let shared = shared.clone();
+                tokio::spawn(async move {
+                    for i in 0..5u32 {
+                        shared.insert(TestO((t * 5 + i) * 10)).await.unwrap();
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(shared.len().await, 20);
+        
+        let id = shared.read().await.id_at(0).unwrap();
+        shared.modify(id, |e| e.0 = 1000).await.unwrap();
+        assert_eq!(shared.get_with_id(id).await, Some(TestO(1000)));
+        
+        let path = std::env::temp_dir().join(format!("obj-alloc-async-{}.json", std::process::id()));
+        shared.save(&path).await.unwrap();
+        let loaded: AsyncOrdIdMap<DefaultId, TestO, u32> = AsyncOrdIdMap::load(&path).await.unwrap();
+        let _ = std::fs::remove_file(&path);
+        
+        assert_eq!(loaded.get_with_id(id).await, Some(TestO(1000)));
+        assert_eq!(loaded.read().await.id_map.max_id(), shared.read().await.id_map.max_id());
+        assert_eq!(loaded.try_unwrap().unwrap().len(), 20);
+    }
+}
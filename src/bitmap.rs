@@ -0,0 +1,121 @@
+//! 占用位图：按固定分辨率描述 span 内哪些格子被占用
+
+use field_collex::{Collexetable, FieldValue};
+use crate::{Id, OrdIdMap};
+
+/// 定长位图，第 i 位对应从 span 起点开始的第 i 个格子
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct OccupancyBitmap {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl OccupancyBitmap {
+    fn push(&mut self, bit: bool) {
+        if self.len.is_multiple_of(64) {
+            self.words.push(0);
+        }
+        if bit {
+            self.words[self.len / 64] |= 1 << (self.len % 64);
+        }
+        self.len += 1;
+    }
+    
+    /// 第 i 位，越界时返回 None
+    pub fn get(&self, i: usize) -> Option<bool> {
+        (i < self.len).then(|| self.words[i / 64] >> (i % 64) & 1 == 1)
+    }
+    
+    /// 位数
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    
+    /// 被置位（占用）的位数
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+    
+    /// 按顺序遍历所有位
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(|i| self.words[i / 64] >> (i % 64) & 1 == 1)
+    }
+    
+    /// 底层的 u64 字，低位在前；最后一个字中超出 `len` 的位恒为 0
+    pub fn as_words(&self) -> &[u64] {
+        &self.words
+    }
+}
+
+impl FromIterator<bool> for OccupancyBitmap {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut bitmap = Self::default();
+        for bit in iter {
+            bitmap.push(bit);
+        }
+        bitmap
+    }
+}
+
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    /// 导出占用位图：从 span 起点按 `resolution` 划分格子，格子内存在元素即置位
+    ///
+    /// 以 unit 为分辨率时每一位即一个槽位；无界 span 得到空位图。`resolution` 不为正时 panic
+    pub fn occupancy_bitmap(&self, resolution: V) -> OccupancyBitmap {
+        self.cells(resolution)
+            .into_iter()
+            .flatten()
+            .map(|(_, occupied)| occupied)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use span_core::Span;
+    use crate::{DefaultId, OrdIdMap};
+    use crate::test_util::{TestO, new_map};
+    
+    #[test]
+    fn test_occupancy_bitmap() {
+        let mut map = new_map();
+        for v in [0, 10, 30, 60] {
+            map.insert(TestO(v)).unwrap();
+        }
+        
+        let bitmap = map.occupancy_bitmap(10);
+        assert_eq!(bitmap.len(), 10);
+        assert_eq!(bitmap.count_ones(), 4);
+        assert_eq!(bitmap.as_words(), &[0b100_1011]);
+        assert_eq!(bitmap.get(3), Some(true));
+        assert_eq!(bitmap.get(10), None);
+        
+        let coarse: Vec<bool> = map.occupancy_bitmap(50).iter().collect();
+        assert_eq!(coarse, vec![true, true]);
+    }
+    
+    #[test]
+    fn test_occupancy_bitmap_near_max() {
+        let mut map: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::new(Span::new_finite(0u32, u32::MAX), 1 << 30).unwrap();
+        map.insert(TestO(u32::MAX - 1)).unwrap();
+        
+        // 最后一格的 `start + resolution` 超出 u32
+        let bitmap: Vec<bool> = map.occupancy_bitmap(3_000_000_000).iter().collect();
+        assert_eq!(bitmap, vec![false, true]);
+    }
+    
+    #[test]
+    #[should_panic(expected = "cell width must be positive")]
+    fn test_occupancy_bitmap_zero_resolution() {
+        let _ = new_map().occupancy_bitmap(0);
+    }
+}
@@ -0,0 +1,144 @@
+//! OrdIdMap 的构造器：把 new / with_capacity / with_elements 与各项可选配置合并为链式调用
+
+use field_collex::{Collexetable, FieldCollex, FieldValue};
+use span_core::Span;
+use crate::{extend_from_iter, BuildError, Id, IdMap, OrdIdMap, ShrinkPolicy};
+
+/// `OrdIdMap::builder()` 返回的构造器，span 与 unit 为必填项
+#[derive(Debug)]
+pub struct OrdIdMapBuilder<K, E, V> {
+    span: Option<Span<V>>,
+    unit: Option<V>,
+    capacity: usize,
+    start_id: Option<K>,
+    elements: Option<Vec<E>>,
+    indexed: bool,
+    shrink_policy: ShrinkPolicy,
+}
+
+impl<K, E, V> Default for OrdIdMapBuilder<K, E, V> {
+    fn default() -> Self {
+        Self {
+            span: None,
+            unit: None,
+            capacity: 0,
+            start_id: None,
+            elements: None,
+            indexed: true,
+            shrink_policy: ShrinkPolicy::default(),
+        }
+    }
+}
+
+impl<K, E, V> OrdIdMapBuilder<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    pub fn span(mut self, span: Span<V>) -> Self {
+        self.span = Some(span);
+        self
+    }
+    
+    pub fn unit(mut self, unit: V) -> Self {
+        self.unit = Some(unit);
+        self
+    }
+    
+    /// 预留的元素容量；同时给出初始元素时，只为 id_map 在初始元素之外再预留，
+    /// collex 的容量由初始元素决定（FieldCollex 没有 reserve）
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+    
+    /// 第一个自动分配的 Id（默认从 1 开始）
+    pub fn start_id(mut self, id: K) -> Self {
+        self.start_id = Some(id);
+        self
+    }
+    
+    /// 初始元素，按顺序从起始 Id 开始分配 Id
+    pub fn elements(mut self, elements: Vec<E>) -> Self {
+        self.elements = Some(elements);
+        self
+    }
+    
+    /// 是否维护 id_map，见 `OrdIdMap::without_index`
+    pub fn indexed(mut self, indexed: bool) -> Self {
+        self.indexed = indexed;
+        self
+    }
+    
+    pub fn shrink_policy(mut self, policy: ShrinkPolicy) -> Self {
+        self.shrink_policy = policy;
+        self
+    }
+    
+    pub fn build(self) -> Result<OrdIdMap<K, E, V>, BuildError<V>> {
+        let span = self.span.ok_or(BuildError::MissingSpan)?;
+        let unit = self.unit.ok_or(BuildError::MissingUnit)?;
+        
+        let initial = self.elements.as_ref().map_or(0, Vec::len);
+        let mut id_map = IdMap::with_id_capacity(if self.indexed { initial + self.capacity } else { 0 });
+        if let Some(start) = self.start_id {
+            id_map.set_max_id(K::from_u64(start.as_u64().saturating_sub(1)));
+        }
+        let collex = match self.elements {
+            Some(elements) => {
+                let pairs = extend_from_iter(&mut id_map, elements, self.indexed);
+                FieldCollex::with_elements(span, unit, pairs).map_err(BuildError::WithElements)?
+            }
+            None if self.capacity > 0 => {
+                FieldCollex::with_capacity(span, unit, self.capacity).map_err(BuildError::WithCapacity)?
+            }
+            None => FieldCollex::new(span, unit).map_err(BuildError::Invalid)?,
+        };
+        
+        let mut map = OrdIdMap::from_raw_parts(id_map, collex);
+        map.indexed = self.indexed;
+        map.shrink_policy = self.shrink_policy;
+        Ok(map)
+    }
+}
+
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    /// 链式配置并构造 OrdIdMap
+    pub fn builder() -> OrdIdMapBuilder<K, E, V> {
+        OrdIdMapBuilder::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use span_core::Span;
+    use crate::{BuildError, DefaultId, OrdIdMap, ShrinkPolicy};
+    use crate::test_util::TestO;
+    
+    #[test]
+    fn test_builder() {
+        let mut map: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::builder()
+            .span(Span::new_finite(0u32, 100u32))
+            .unit(10)
+            .capacity(8)
+            .start_id(DefaultId(100))
+            .elements(vec![TestO(10), TestO(20)])
+            .shrink_policy(ShrinkPolicy::Always)
+            .build()
+            .unwrap();
+        assert_eq!(map.ids().collect::<Vec<_>>(), vec![DefaultId(100), DefaultId(101)]);
+        assert_eq!(map.shrink_policy(), ShrinkPolicy::Always);
+        assert!(map.id_map().capacity() >= 10);
+        assert_eq!(map.insert(TestO(30)).unwrap(), DefaultId(102));
+        assert!(map.validate().is_ok());
+        
+        let missing = OrdIdMap::<DefaultId, TestO, u32>::builder().unit(10).build();
+        assert!(matches!(missing, Err(BuildError::MissingSpan)));
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use crate::{Id, PairMeta};
+
+/// 变更类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeKind {
+    Inserted,
+    Modified,
+    Removed,
+}
+
+/// 变更记录：单调递增的版本号 + 每个 Id 最后一次变更的版本与类型
+///
+/// 同一 Id 多次变更只保留最后一次；已删除的 Id 也会保留记录，可用 `forget_before` 清理
+#[derive(Debug, Clone)]
+pub struct ChangeLog<K: Id> {
+    version: u64,
+    entries: HashMap<u64, (u64, ChangeKind)>,
+    meta: Option<HashMap<u64, PairMeta>>,
+    _marker: PhantomData<K>,
+}
+
+impl<K: Id> Default for ChangeLog<K> {
+    fn default() -> Self {
+        Self {
+            version: 0,
+            entries: HashMap::new(),
+            meta: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K: Id> ChangeLog<K> {
+    /// 当前版本号，每次变更加一
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+    
+    pub(crate) fn record(&mut self, id: K, kind: ChangeKind) {
+        self.version += 1;
+        self.entries.insert(id.as_u64(), (self.version, kind));
+        if let Some(meta) = &mut self.meta {
+            match kind {
+                ChangeKind::Inserted => {
+                    meta.insert(id.as_u64(), PairMeta::new(self.version));
+                }
+                ChangeKind::Modified => {
+                    meta.entry(id.as_u64())
+                        .or_insert_with(|| PairMeta::new(self.version))
+                        .modified_version = self.version;
+                }
+                ChangeKind::Removed => {
+                    meta.remove(&id.as_u64());
+                }
+            }
+        }
+    }
+    
+    pub(crate) fn enable_meta(&mut self, ids: impl IntoIterator<Item = K>) {
+        let meta = self.meta.get_or_insert_with(HashMap::new);
+        for id in ids {
+            meta.entry(id.as_u64()).or_insert_with(|| PairMeta::new(self.version));
+        }
+    }
+    
+    pub(crate) fn disable_meta(&mut self) {
+        self.meta = None;
+    }
+    
+    pub(crate) fn meta(&self, id: K) -> Option<&PairMeta> {
+        self.meta.as_ref()?.get(&id.as_u64())
+    }
+    
+    pub(crate) fn meta_mut(&mut self, id: K) -> Option<&mut PairMeta> {
+        self.meta.as_mut()?.get_mut(&id.as_u64())
+    }
+    
+    /// 版本号大于 `version` 的所有变更（顺序不保证）
+    pub fn changed_since(&self, version: u64) -> impl Iterator<Item = (K, ChangeKind)> {
+        self.entries
+            .iter()
+            .filter(move |(_, (v, _))| *v > version)
+            .map(|(id, (_, kind))| (K::from_u64(*id), *kind))
+    }
+    
+    /// 丢弃版本号不大于 `version` 的记录
+    pub fn forget_before(&mut self, version: u64) {
+        self.entries.retain(|_, (v, _)| *v > version);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ChangeKind;
+    use crate::test_util::{TestO, new_map};
+    
+    #[test]
+    fn test_changed_since() {
+        let mut map = new_map();
+        let a = map.insert(TestO(10)).unwrap();
+        let b = map.insert(TestO(20)).unwrap();
+        let seen = map.version();
+        
+        map.modify(a, |e| e.0 = 30).unwrap();
+        map.remove(b);
+        let c = map.insert(TestO(40)).unwrap();
+        
+        let mut changes: Vec<_> = map.changed_since(seen).collect();
+        changes.sort_by_key(|(id, _)| id.0);
+        assert_eq!(changes, vec![
+            (a, ChangeKind::Modified),
+            (b, ChangeKind::Removed),
+            (c, ChangeKind::Inserted),
+        ]);
+        assert_eq!(map.changed_since(map.version()).count(), 0);
+    }
+}
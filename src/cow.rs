@@ -0,0 +1,316 @@
+//! 写时复制的 OrdIdMap：按 field 值分块存储，各块由 Arc 共享，克隆只复制块指针，修改时只复制涉及的块；
+//! 同样借此提供与写入并行的只读快照
+
+use std::sync::Arc;
+use field_collex::{Collexetable, FieldValue};
+use field_collex::collex::InsertFieldCollexError;
+use span_core::Span;
+use crate::sharded::{segment_index, split_span};
+use crate::{Id, ModifyManyError, NewShardedError, OrdIdMap, ReplaceError};
+
+/// 写时复制的 OrdIdMap，适合频繁克隆以便回滚的场景
+///
+/// 克隆是 O(1) 的；修改一个元素只复制它所在（以及移入）的块，其余块在各克隆之间继续共享。
+/// 按 Id 的查询需要依次查找各块
+#[derive(Debug, Clone)]
+pub struct CowOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V> + Clone,
+    V: FieldValue,
+{
+    /// 各块的起始值，升序
+    starts: Arc<[V]>,
+    end: V,
+    unit: V,
+    chunks: Arc<Vec<Arc<OrdIdMap<K, E, V>>>>,
+    max_id: u64,
+    /// 每次成功的修改加一
+    version: u64,
+}
+
+impl<K, E, V> CowOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V> + Clone,
+    V: FieldValue,
+{
+    /// 把 span 按 unit 对齐切成至多 `chunk_count` 块
+    pub fn new(span: Span<V>, unit: V, chunk_count: usize) -> Result<Self, NewShardedError<V>> {
+        let (starts, end, maps) = split_span(span, unit, chunk_count)?;
+        Ok(Self {
+            starts: starts.into(),
+            end,
+            unit,
+            chunks: Arc::new(maps.into_iter().map(Arc::new).collect()),
+            max_id: 0,
+            version: 0,
+        })
+    }
+    
+    /// 由现有 OrdIdMap 构建，Id 与 max_id 保持不变
+    pub fn from_map(map: OrdIdMap<K, E, V>, chunk_count: usize) -> Result<Self, NewShardedError<V>> {
+        let mut cow = Self::new(map.span().clone(), *map.unit(), chunk_count)?;
+        cow.max_id = map.id_map.max_id().as_u64();
+        for (id, elem) in map.into_vec() {
+            let index = cow.chunk_index(elem.collexate_ref()).expect("element lies in the span");
+            if cow.chunk_mut(index).insert_with_id(id, elem).is_err() {
+                panic!("element does not fit its chunk");
+            }
+        }
+        Ok(cow)
+    }
+    
+    /// 合并回单个 OrdIdMap，Id 与 max_id 保持不变
+    pub fn into_map(self) -> OrdIdMap<K, E, V> {
+        let Ok(mut map) = OrdIdMap::new(Span::new_finite(self.starts[0], self.end), self.unit) else {
+            unreachable!("span validated in CowOrdIdMap::new")
+        };
+        map.reserve(self.len());
+        for chunk in Arc::unwrap_or_clone(self.chunks) {
+            for (id, elem) in Arc::unwrap_or_clone(chunk).into_vec() {
+                if map.insert_with_id(id, elem).is_err() {
+                    panic!("chunk element does not fit the merged map");
+                }
+            }
+        }
+        map.id_map.bump_max_id(K::from_u64(self.max_id));
+        map
+    }
+    
+    /// 当前版本号，每次成功的修改加一
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+    
+    /// 当前状态的只读快照，O(1)
+    ///
+    /// 之后对本 map 的修改只复制涉及的块，不影响快照；快照可发送到其他线程读取
+    pub fn read_snapshot(&self) -> ReadSnapshot<K, E, V> {
+        ReadSnapshot {
+            inner: self.clone(),
+        }
+    }
+    
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+    
+    /// field 值所属的块，超出 span 返回 None
+    pub fn chunk_index(&self, value: &V) -> Option<usize> {
+        segment_index(&self.starts, self.end, value)
+    }
+    
+    /// 与 `other` 共享（未被复制）的块数
+    pub fn shared_chunks(&self, other: &Self) -> usize {
+        self.chunks
+            .iter()
+            .zip(other.chunks.iter())
+            .filter(|(a, b)| Arc::ptr_eq(a, b))
+            .count()
+    }
+    
+    /// 块的可变访问，若该块仍被其他克隆共享则先复制
+    fn chunk_mut(&mut self, index: usize) -> &mut OrdIdMap<K, E, V> {
+        Arc::make_mut(&mut Arc::make_mut(&mut self.chunks)[index])
+    }
+    
+    fn chunk_of_id(&self, id: K) -> Option<usize> {
+        self.chunks.iter().position(|chunk| chunk.contains_id(id))
+    }
+    
+    pub fn insert(&mut self, elem: E) -> Result<K, InsertFieldCollexError<E>> {
+        let Some(index) = self.chunk_index(elem.collexate_ref()) else {
+            return Err(InsertFieldCollexError::OutOfSpan(elem));
+        };
+        let id = K::from_u64(self.max_id + 1);
+        self.chunk_mut(index).insert_with_id(id, elem)?;
+        self.max_id += 1;
+        self.version += 1;
+        Ok(id)
+    }
+    
+    pub fn remove(&mut self, id: K) -> Option<E> {
+        let index = self.chunk_of_id(id)?;
+        let removed = self.chunk_mut(index).remove(id);
+        self.version += 1;
+        removed
+    }
+    
+    /// 修改元素，field 值离开原块时会移到新的块；失败时元素保持修改前的值
+    pub fn modify<F, R>(&mut self, id: K, f: F) -> Result<R, ModifyManyError>
+    where
+        F: FnOnce(&mut E) -> R,
+    {
+        let src = self.chunk_of_id(id).ok_or(ModifyManyError::CannotFind)?;
+        let mut elem = self.chunks[src].get_with_id(id).cloned().ok_or(ModifyManyError::CannotFind)?;
+        let r = f(&mut elem);
+        let dst = self.chunk_index(elem.collexate_ref()).ok_or(ModifyManyError::OutOfSpan)?;
+        
+        if src == dst {
+            return self.chunk_mut(src).replace(id, elem).map(|_| {
+                self.version += 1;
+                r
+            }).map_err(|err| match err {
+                ReplaceError::CannotFind(_) => ModifyManyError::CannotFind,
+                ReplaceError::OutOfSpan(_) => ModifyManyError::OutOfSpan,
+                ReplaceError::AlreadyExist(_) => ModifyManyError::AlreadyExist,
+            });
+        }
+        match self.chunk_mut(dst).insert_with_id(id, elem) {
+            Ok(_) => {
+                self.chunk_mut(src).remove(id);
+                self.version += 1;
+                Ok(r)
+            }
+            Err(InsertFieldCollexError::OutOfSpan(_)) => Err(ModifyManyError::OutOfSpan),
+            Err(InsertFieldCollexError::AlreadyExist(_)) => Err(ModifyManyError::AlreadyExist),
+        }
+    }
+    
+    pub fn get_with_id(&self, id: K) -> Option<&E> {
+        self.chunks.iter().find_map(|chunk| chunk.get_with_id(id))
+    }
+    
+    pub fn contains_id(&self, id: K) -> bool {
+        self.chunk_of_id(id).is_some()
+    }
+    
+    /// 按 field 值查询 Id
+    pub fn id_at(&self, value: V) -> Option<K> {
+        self.chunks[self.chunk_index(&value)?].id_at(value)
+    }
+    
+    /// 按 field 顺序遍历
+    pub fn iter(&self) -> impl Iterator<Item = (K, &E)> {
+        self.chunks.iter().flat_map(|chunk| chunk.iter())
+    }
+    
+    pub fn len(&self) -> usize {
+        self.chunks.iter().map(|chunk| chunk.len()).sum()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.chunks.iter().all(|chunk| chunk.is_empty())
+    }
+}
+
+/// `CowOrdIdMap::read_snapshot` 得到的一致只读视图，与写入方共享未修改的块
+#[derive(Debug, Clone)]
+pub struct ReadSnapshot<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V> + Clone,
+    V: FieldValue,
+{
+    inner: CowOrdIdMap<K, E, V>,
+}
+
+impl<K, E, V> ReadSnapshot<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V> + Clone,
+    V: FieldValue,
+{
+    /// 拍摄快照时的版本号
+    pub fn version(&self) -> u64 {
+        self.inner.version()
+    }
+    
+    pub fn get_with_id(&self, id: K) -> Option<&E> {
+        self.inner.get_with_id(id)
+    }
+    
+    pub fn contains_id(&self, id: K) -> bool {
+        self.inner.contains_id(id)
+    }
+    
+    pub fn id_at(&self, value: V) -> Option<K> {
+        self.inner.id_at(value)
+    }
+    
+    /// 按 field 顺序遍历
+    pub fn iter(&self) -> impl Iterator<Item = (K, &E)> {
+        self.inner.iter()
+    }
+    
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use span_core::Span;
+    use crate::{DefaultId, ModifyManyError, OrdIdMap};
+    use super::CowOrdIdMap;
+    use crate::test_util::TestO;
+    
+    #[test]
+    fn test_cow_clone_shares_untouched_chunks() {
+        let map: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::from_iter_with(
+            Span::new_finite(0u32, 1000u32),
+            10,
+            (0..100u32).map(|i| TestO(i * 10)),
+        ).unwrap();
+        let original = CowOrdIdMap::from_map(map.clone(), 10).unwrap();
+        assert_eq!(original.chunk_count(), 10);
+        
+        let mut sim = original.clone();
+        assert_eq!(sim.shared_chunks(&original), 10);
+        
+        let id = sim.id_at(0).unwrap();
+        sim.modify(id, |e| e.0 = 5).unwrap();
+        assert_eq!(sim.shared_chunks(&original), 9);
+        // 目标位置被占用时保持原值
+        assert_eq!(sim.modify(id, |e| e.0 = 990), Err(ModifyManyError::AlreadyExist));
+        assert_eq!(sim.get_with_id(id), Some(&TestO(5)));
+        
+        let last = sim.id_at(990).unwrap();
+        sim.remove(last);
+        assert_eq!(sim.shared_chunks(&original), 8);
+        // 移到另一块
+        sim.modify(id, |e| e.0 = 990).unwrap();
+        assert_eq!(sim.shared_chunks(&original), 8);
+        assert_eq!(sim.id_at(990), Some(id));
+        
+        assert_eq!(original.get_with_id(id), Some(&TestO(0)));
+        assert_eq!(sim.get_with_id(id), Some(&TestO(990)));
+        assert_eq!(original.clone().into_map(), map);
+        
+        let restored = sim.into_map();
+        assert_eq!(restored.len(), 99);
+        assert_eq!(restored.id_map.max_id(), map.id_map.max_id());
+        assert!(restored.validate().is_ok());
+    }
+    
+    #[test]
+    fn test_read_snapshot_concurrent_with_writes() {
+        let mut cow: CowOrdIdMap<DefaultId, TestO, u32> =
+            CowOrdIdMap::new(Span::new_finite(0u32, 1000u32), 10, 8).unwrap();
+        for i in 0..50u32 {
+            cow.insert(TestO(i * 10)).unwrap();
+        }
+        let snap = cow.read_snapshot();
+        assert_eq!(snap.version(), 50);
+        
+        let reader = std::thread::spawn(move || {
+            let values: Vec<u32> = snap.iter().map(|(_, e)| e.0).collect();
+            (snap.version(), values)
+        });
+        for i in 0..50u32 {
+            let id = cow.id_at(i * 10).unwrap();
+            cow.modify(id, |e| e.0 += 500).unwrap();
+        }
+        
+        let (version, values) = reader.join().unwrap();
+        assert_eq!(version, 50);
+        assert_eq!(values, (0..50u32).map(|i| i * 10).collect::<Vec<_>>());
+        assert_eq!(cow.version(), 100);
+        assert!(cow.iter().all(|(_, e)| e.0 >= 500));
+    }
+}
@@ -0,0 +1,146 @@
+use field_collex::{Collexetable, FieldValue};
+use field_collex::collex::InsertFieldCollexError;
+use crate::{Id, InsertAfterError, ModifyGuard, OrdIdMap};
+
+/// 按 field 顺序游走的可变游标，删除与插入时同步 collex 与 id_map
+///
+/// 游标按 Id 跟踪当前元素；越过首尾后停在“空位”，此时再移动会回到另一端
+pub struct CursorMut<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    map: &'a mut OrdIdMap<K, E, V>,
+    current: Option<K>,
+}
+
+impl<'a, K, E, V> CursorMut<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    pub(crate) fn new(map: &'a mut OrdIdMap<K, E, V>, current: Option<K>) -> Self {
+        Self { map, current }
+    }
+    
+    /// 当前元素的 field 值，游标位于空位时为 None
+    fn current_value(&self) -> Option<V> {
+        self.map.locate(self.current?)
+    }
+    
+    /// 当前元素，游标位于空位时为 None
+    pub fn current(&self) -> Option<(K, &E)> {
+        let id = self.current?;
+        self.map.get_with_id(id).map(|e| (id, e))
+    }
+    
+    /// 当前元素的可变访问守卫，释放时重新 collexate；游标继续跟随该元素
+    pub fn current_mut(&mut self) -> Option<ModifyGuard<'_, K, E, V>> {
+        self.map.get_mut_with_id(self.current?)
+    }
+    
+    /// 下一个元素（不移动游标）
+    pub fn peek_next(&self) -> Option<(K, &E)> {
+        match self.current_value() {
+            Some(v) => self.map.iter().find(|(_, e)| *e.collexate_ref() > v),
+            None => self.map.iter().next(),
+        }
+    }
+    
+    /// 上一个元素（不移动游标）
+    pub fn peek_prev(&self) -> Option<(K, &E)> {
+        match self.current_value() {
+            Some(v) => self.map.iter().take_while(|(_, e)| *e.collexate_ref() < v).last(),
+            None => self.map.iter().last(),
+        }
+    }
+    
+    /// 移到下一个元素；越过末尾后停在空位
+    pub fn move_next(&mut self) {
+        self.current = self.peek_next().map(|(id, _)| id);
+    }
+    
+    /// 移到上一个元素；越过开头后停在空位
+    pub fn move_prev(&mut self) {
+        self.current = self.peek_prev().map(|(id, _)| id);
+    }
+    
+    /// 删除当前元素，游标移到其下一个元素
+    pub fn remove_current(&mut self) -> Option<(K, E)> {
+        let id = self.current?;
+        self.move_next();
+        self.map.remove(id).map(|e| (id, e))
+    }
+    
+    /// 在当前元素之后插入，游标不动；游标位于空位时插入到最前
+    ///
+    /// 元素位置由 field 值决定，因此其 field 值必须落在当前元素与下一个元素之间
+    pub fn insert_after(&mut self, elem: E) -> Result<K, InsertAfterError<E>> {
+        let v = elem.collexate();
+        let after_current = self.current_value().is_none_or(|cur| v > cur);
+        let before_next = self.peek_next().is_none_or(|(_, e)| v < *e.collexate_ref());
+        if !(after_current && before_next) {
+            return Err(InsertAfterError::OutOfOrder(elem));
+        }
+        self.map.insert(elem).map_err(|err| match err {
+            InsertFieldCollexError::OutOfSpan(e) => InsertAfterError::OutOfSpan(e),
+            InsertFieldCollexError::AlreadyExist(e) => InsertAfterError::AlreadyExist(e),
+        })
+    }
+}
+
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    /// 指向 field 值最小的元素的游标
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, K, E, V> {
+        let first = self.ids().next();
+        CursorMut::new(self, first)
+    }
+    
+    /// 指向 Id 对应元素的游标，Id 不存在时返回 None
+    pub fn cursor_mut_at(&mut self, id: K) -> Option<CursorMut<'_, K, E, V>> {
+        self.ensure_index();
+        if !self.contains_id(id) {
+            return None;
+        }
+        Some(CursorMut::new(self, Some(id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::InsertAfterError;
+    use crate::test_util::{TestO, new_map};
+    
+    #[test]
+    fn test_cursor_mut() {
+        let mut map = new_map();
+        let a = map.insert(TestO(10)).unwrap();
+        let b = map.insert(TestO(20)).unwrap();
+        let c = map.insert(TestO(40)).unwrap();
+        
+        let mut cursor = map.cursor_front_mut();
+        assert_eq!(cursor.current().map(|(id, _)| id), Some(a));
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some((b, TestO(20))));
+        assert_eq!(cursor.current().map(|(id, _)| id), Some(c));
+        
+        cursor.move_prev();
+        assert!(matches!(cursor.insert_after(TestO(50)), Err(InsertAfterError::OutOfOrder(_))));
+        let d = cursor.insert_after(TestO(30)).unwrap();
+        cursor.move_next();
+        assert_eq!(cursor.current().map(|(id, _)| id), Some(d));
+        cursor.move_next();
+        cursor.move_next();
+        assert!(cursor.current().is_none());
+        
+        assert_eq!(map.ids().collect::<Vec<_>>(), vec![a, d, c]);
+        assert!(!map.contains_id(b));
+    }
+}
@@ -0,0 +1,132 @@
+//! 增量序列化：只编码某个版本之后发生变化的 Id，用于频繁的自动保存
+
+use std::collections::HashMap;
+use field_collex::{Collexetable, FieldValue};
+use field_collex::collex::InsertFieldCollexError;
+use serde::{Deserialize, Serialize};
+use crate::{ChangeKind, Id, OrdIdMap};
+
+/// 自 `since` 版本以来的增量
+///
+/// 由 `serialize_delta` 生成时 `E` 为元素引用；反序列化得到拥有所有权的元素后交给 `apply_delta`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Delta<K, E> {
+    /// 增量的起始版本
+    pub since: u64,
+    /// 生成增量时的版本
+    pub version: u64,
+    /// 生成增量时的 max_id
+    pub max_id: K,
+    /// 新插入或被修改过的元素的最新值
+    pub upserts: Vec<(K, E)>,
+    /// 被删除的 Id
+    pub removed: Vec<K>,
+}
+
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    /// 生成自版本 `since` 以来的增量，结果可直接序列化
+    ///
+    /// 依赖变更记录，`since` 之后的记录不能已被 `forget_changes_before` 丢弃
+    pub fn serialize_delta(&self, since: u64) -> Delta<K, &E> {
+        let mut upserts = Vec::new();
+        let mut removed = Vec::new();
+        for (id, kind) in self.changed_since(since) {
+            match (kind, self.get_with_id(id)) {
+                (ChangeKind::Inserted | ChangeKind::Modified, Some(elem)) => upserts.push((id, elem)),
+                (ChangeKind::Removed, None) => removed.push(id),
+                _ => {}
+            }
+        }
+        upserts.sort_by_key(|(id, _)| id.as_u64());
+        removed.sort_by_key(|id| id.as_u64());
+        Delta {
+            since,
+            version: self.version(),
+            max_id: self.id_map.max_id(),
+            upserts,
+            removed,
+        }
+    }
+    
+    /// 应用增量：先删除，再移出所有待更新的旧元素，最后统一放入新值
+    ///
+    /// 放不进去的元素连同原因返回，该 Id 的旧元素原样放回；其余部分照常生效。
+    /// 旧元素的位置已被本次增量中的其他新值占据时无法放回
+    pub fn apply_delta(&mut self, delta: Delta<K, E>) -> Result<(), Vec<(K, InsertFieldCollexError<E>)>> {
+        for id in delta.removed {
+            self.remove(id);
+        }
+        let mut previous = HashMap::with_capacity(delta.upserts.len());
+        for (id, _) in &delta.upserts {
+            if let Some(old) = self.remove(*id) {
+                previous.insert(id.as_u64(), old);
+            }
+        }
+        let errors: Vec<_> = delta.upserts
+            .into_iter()
+            .filter_map(|(id, elem)| self.insert_with_id(id, elem).err().map(|err| (id, err)))
+            .collect();
+        for (id, _) in &errors {
+            if let Some(old) = previous.remove(&id.as_u64()) {
+                let _ = self.insert_with_id(*id, old);
+            }
+        }
+        self.id_map.bump_max_id(delta.max_id);
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DefaultId, Delta};
+    use crate::test_util::{TestO, new_map};
+    
+    #[test]
+    fn test_delta_round_trip() {
+        let mut map = new_map();
+        let a = map.insert(TestO(10)).unwrap();
+        let b = map.insert(TestO(20)).unwrap();
+        let mut replica = map.clone();
+        let saved = map.version();
+        
+        // a 挪到 b 原来的位置，需要先移出 b 才能放入
+        map.remove(b);
+        map.modify(a, |e| e.0 = 20).unwrap();
+        let c = map.insert(TestO(50)).unwrap();
+        map.remove(c);
+        
+        let json = serde_json::to_string(&map.serialize_delta(saved)).unwrap();
+        let delta: Delta<DefaultId, TestO> = serde_json::from_str(&json).unwrap();
+        assert_eq!(delta.upserts, vec![(a, TestO(20))]);
+        assert_eq!(delta.removed, vec![b, c]);
+        
+        replica.apply_delta(delta).unwrap();
+        assert_eq!(replica, map);
+        assert_eq!(replica.id_map.max_id(), c);
+    }
+    
+    #[test]
+    fn test_delta_rejected_upsert_keeps_old() {
+        let mut map = new_map();
+        let a = map.insert(TestO(10)).unwrap();
+        let b = map.insert(TestO(20)).unwrap();
+        
+        let delta = Delta {
+            since: 0,
+            version: 0,
+            max_id: b,
+            upserts: vec![(a, TestO(200)), (b, TestO(30))],
+            removed: vec![],
+        };
+        let errors = map.apply_delta(delta).unwrap_err();
+        assert_eq!(errors.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![a]);
+        assert_eq!(map.get_with_id(a), Some(&TestO(10)));
+        assert_eq!(map.get_with_id(b), Some(&TestO(30)));
+        assert!(map.validate().is_ok());
+    }
+}
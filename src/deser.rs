@@ -1,74 +1,201 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::marker::PhantomData;
 use field_collex::{Collexetable, FieldCollex, FieldValue};
-use field_collex::collex::serialize::{FieldCollexSerdeHelper, FieldCollexSerdeWrapper};
+use field_collex::collex::InsertFieldCollexError;
+use field_collex::collex::serialize::{default_span, default_unit, FieldCollexSerdeHelper, FieldCollexSerdeWrapper};
 use serde::{Deserialize, Deserializer};
+use serde::de::DeserializeSeed;
 use serde::de::Error;
-use crate::{Id, IdMap, OrdIdMap};
+use span_core::Span;
+use crate::{build_index, Id, LoadError, OrdIdMap};
 use crate::pair::Pair;
 
-impl<'de, K, O, T> Deserialize<'de> for OrdIdMap<K, O, T>
+/// 未经校验的序列化内容
+///
+/// 反序列化 OrdIdMap 失败时只能得到格式化后的错误信息；先反序列化成本类型，
+/// 再用 `OrdIdMap::try_from` 即可得到指明出错元素的 `LoadError`
+#[derive(Debug, Clone)]
+pub struct UncheckedOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    pub span: Span<V>,
+    pub unit: V,
+    pub elements: Vec<Pair<K, E>>,
+}
+
+impl<'de, K, E, V> Deserialize<'de> for UncheckedOrdIdMap<K, E, V>
 where
-    O: Collexetable<T> + Deserialize<'de>,
-    T: FieldValue + Deserialize<'de>,
     K: Id + Deserialize<'de>,
+    E: Collexetable<V> + Deserialize<'de>,
+    V: FieldValue + Deserialize<'de>,
 {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        // 核心优化1：直接复用 FieldCollexSerdeHelper，避免二次解析 FieldCollex
-        // （原逻辑是先解析 FieldCollex，再从 FieldCollex 取元素；现在直接解析到 Helper，提前拿到结构化数据）
-        let collex_helper: FieldCollexSerdeHelper<Pair<K,O>, T> = FieldCollexSerdeWrapper::<Pair<K,O>, T>::deserialize(deserializer)
-            .map_err(|err|D::Error::custom(format!("反序列化 FieldCollexSerdeHelper 失败: {}", err)))?
+        // 非自描述格式（bincode、postcard 等）不支持无标签枚举所需的 deserialize_any，
+        // 按序列化时输出的纯数组读取，span 与 unit 取默认值
+        if !deserializer.is_human_readable() {
+            let elements = Vec::<Pair<K, E>>::deserialize(deserializer)?;
+            return Ok(Self {
+                span: default_span(),
+                unit: default_unit(),
+                elements,
+            });
+        }
+        // 底层格式的错误原样传出，不转成字符串，保留各格式自身的错误信息（位置等）
+        let helper: FieldCollexSerdeHelper<Pair<K,E>, V> = FieldCollexSerdeWrapper::<Pair<K,E>, V>::deserialize(deserializer)?
             .into();
-        
-        // 核心优化2：利用 elements 的长度预分配 IdMap 容量，避免 HashMap 动态扩容（性能提升关键）
-        let elements_len = collex_helper.elements.len();
-        let mut id_map = IdMap::<K, T>::with_id_capacity(elements_len);
-        
-        // 核心优化3：直接遍历预解析的 Vec<E>，而非通过 FieldCollex 迭代器（减少迭代器开销）
-        // 遍历过程中无额外内存分配，直接操作已有 Vec
-        for obj in collex_helper.elements.iter() {
-            let obj_id = obj.0; // 取出 Obj 中的 K（Id）
-            let t_value = obj.1.collexate(); // 从 O 生成 T
-            // 插入时无 HashMap 扩容开销（已预分配）
-            id_map.insert_with_id(obj_id, t_value);
+        Ok(Self {
+            span: helper.span,
+            unit: helper.unit,
+            elements: helper.elements,
+        })
+    }
+}
+
+impl<K, E, V> TryFrom<UncheckedOrdIdMap<K, E, V>> for OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    type Error = LoadError<K, V>;
+    
+    /// 逐个校验并放入元素，遇到第一个不合法的元素即返回
+    fn try_from(raw: UncheckedOrdIdMap<K, E, V>) -> Result<Self, Self::Error> {
+        // 重复的 Id 会让 id_map 与 collex 不一致，直接拒绝
+        let mut seen = HashSet::with_capacity(raw.elements.len());
+        if let Some(dup) = raw.elements.iter().find(|p| !seen.insert(p.0.as_u64())) {
+            return Err(LoadError::DuplicateId(dup.0));
         }
         
-        // 还原 FieldCollex（复用已解析的 span/unit/elements，无重复构造）
-        let collex = FieldCollex::with_elements(collex_helper.span, collex_helper.unit, collex_helper.elements)
-            .map_err(|e| D::Error::custom(format!("反序列化时创建 FieldCollex 失败: {}", e)))?;
+        // 利用 elements 的长度预分配 IdMap 容量，避免 HashMap 动态扩容
+        let id_map = build_index(raw.elements.iter(), raw.elements.len());
         
-        Ok(Self {
-            id_map,
-            collex,
-        })
+        let mut collex = FieldCollex::new(raw.span, raw.unit).map_err(LoadError::Invalid)?;
+        for pair in raw.elements {
+            match collex.insert(pair) {
+                Ok(_) => {}
+                Err(InsertFieldCollexError::OutOfSpan(p)) => {
+                    return Err(LoadError::OutOfSpan { id: p.0, value: *p.collexate_ref() });
+                }
+                Err(InsertFieldCollexError::AlreadyExist(p)) => {
+                    let value = *p.collexate_ref();
+                    let occupant = collex.get(value).map(|o| o.0);
+                    return Err(LoadError::AlreadyExist { id: p.0, value, occupant });
+                }
+            }
+        }
+        
+        Ok(Self::from_raw_parts(id_map, collex))
     }
 }
 
+/// 元素只需实现 `Deserialize<'de>`，可直接借用输入缓冲区中的字符串、字节（零拷贝），
+/// 不要求 `DeserializeOwned`
+impl<'de, K, O, T> Deserialize<'de> for OrdIdMap<K, O, T>
+where
+    O: Collexetable<T> + Deserialize<'de>,
+    T: FieldValue + fmt::Debug + Deserialize<'de>,
+    K: Id + Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        UncheckedOrdIdMap::deserialize(deserializer)?
+            .try_into()
+            .map_err(D::Error::custom)
+    }
+}
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue + fmt::Debug,
+{
+    /// 反序列化旧元素类型的存档，并用 `upgrade` 逐个迁移到当前元素类型
+    ///
+    /// Id 保持不变；迁移前后元素的 field 值必须相同，否则报错
+    pub fn deserialize_with_upgrade<'de, D, OldE, F>(deserializer: D, upgrade: F) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+        K: Deserialize<'de>,
+        V: Deserialize<'de>,
+        OldE: Collexetable<V> + Deserialize<'de>,
+        F: Fn(OldE) -> E,
+    {
+        let old = UncheckedOrdIdMap::<K, OldE, V>::deserialize(deserializer)?;
+        let mut elements = Vec::with_capacity(old.elements.len());
+        for Pair(id, elem) in old.elements {
+            let before = *elem.collexate_ref();
+            let elem = upgrade(elem);
+            let after = *elem.collexate_ref();
+            if before != after {
+                return Err(D::Error::custom(format!(
+                    "元素 {:?} 迁移后 field 值由 {:?} 变为 {:?}", id, before, after
+                )));
+            }
+            elements.push(Pair(id, elem));
+        }
+        UncheckedOrdIdMap { span: old.span, unit: old.unit, elements }
+            .try_into()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// 以调用者给定的 span 与 unit 反序列化 OrdIdMap
+///
+/// 序列化内容中的 span、unit 会被忽略；元素放不进给定的 span 时返回指明该元素的错误
+#[derive(Debug, Clone)]
+pub struct OrdIdMapSeed<K, E, V> {
+    pub span: Span<V>,
+    pub unit: V,
+    _marker: PhantomData<(K, E)>,
+}
+
+impl<K, E, V> OrdIdMapSeed<K, E, V> {
+    pub fn new(span: Span<V>, unit: V) -> Self {
+        Self {
+            span,
+            unit,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'de, K, E, V> DeserializeSeed<'de> for OrdIdMapSeed<K, E, V>
+where
+    K: Id + Deserialize<'de>,
+    E: Collexetable<V> + Deserialize<'de>,
+    V: FieldValue + fmt::Debug + Deserialize<'de>,
+{
+    type Value = OrdIdMap<K, E, V>;
+    
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut raw = UncheckedOrdIdMap::deserialize(deserializer)?;
+        raw.span = self.span;
+        raw.unit = self.unit;
+        raw.try_into().map_err(D::Error::custom)
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use field_collex::collex::serialize::{default_span, default_unit};
-    use serde::Serialize;
     use super::*;
     use serde_json;
     use span_core::Span;
-    use crate::DefaultId;
-    
-    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-    pub struct TestO(pub u32);
-    pub type TestT = u32;
-    impl Collexetable<TestT> for TestO {
-        fn collexate(&self) -> TestT { self.0 }
-        
-        fn collexate_ref(&self) -> &TestT {
-            &self.0
-        }
-        
-        fn collexate_mut(&mut self) -> &mut TestT {
-            &mut self.0
-        }
-    }
+    use crate::{DefaultId, FullOrdIdMap, IdMap};
+    use crate::test_util::{TestO, TestT};
     
     // ============================ 2. 功能测试 ============================
     /// 核心功能：验证序列化/反序列化后数据一致性
@@ -83,7 +210,7 @@ mod tests {
             Pair(DefaultId(3), TestO(30)),
         ];
         // 构造 FieldCollex
-        let collex = FieldCollex::with_elements(span.clone(), unit.clone(), elements.clone())
+        let collex = FieldCollex::with_elements(span.clone(), unit, elements.clone())
             .expect("构造 FieldCollex 失败");
         // 构造 IdMap（手动插入与 elements 匹配的 Id/T）
         let mut id_map = IdMap::<DefaultId, TestT>::with_capacity(elements.len());
@@ -91,7 +218,7 @@ mod tests {
             id_map.insert_with_id(obj.0, obj.1.collexate());
         }
         // 原始 ObjAllocator
-        let original = OrdIdMap { id_map, collex };
+        let original = OrdIdMap::from_raw_parts(id_map, collex);
         
         // 步骤2：序列化
         let json = serde_json::to_string(&original).expect("序列化失败");
@@ -127,10 +254,10 @@ mod tests {
         let unit = 1032;
         let collex = FieldCollex::new(span, unit)
             .expect("构造空 FieldCollex 失败");
-        let original: OrdIdMap<DefaultId, TestO, TestT> = OrdIdMap {
-            id_map: IdMap::with_capacity(0),
+        let original: OrdIdMap<DefaultId, TestO, TestT> = OrdIdMap::from_raw_parts(
+            IdMap::with_capacity(0),
             collex,
-        };
+        );
         
         // 序列化 + 反序列化
         let json = serde_json::to_string(&original).unwrap();
@@ -140,4 +267,171 @@ mod tests {
         assert!(deserialized.collex.is_empty());
         assert!(deserialized.id_map.inner.is_empty());
     }
-}
\ No newline at end of file
+    
+    fn sample() -> OrdIdMap<DefaultId, TestO, TestT> {
+        let mut map = OrdIdMap::new(default_span(), default_unit()).unwrap();
+        map.insert(TestO(10)).unwrap();
+        let removed = map.insert(TestO(20)).unwrap();
+        map.insert(TestO(30)).unwrap();
+        map.remove(removed);
+        map
+    }
+    
+    /// 非自描述格式：bincode
+    #[test]
+    fn test_obj_allocator_serde_bincode() {
+        let original = sample();
+        let bytes = bincode::serialize(&original).expect("序列化失败");
+        let deserialized: OrdIdMap<DefaultId, TestO, TestT> = bincode::deserialize(&bytes).expect("反序列化失败");
+        assert_eq!(deserialized, original);
+        
+        let full = FullOrdIdMap(original);
+        let bytes = bincode::serialize(&full).expect("序列化失败");
+        let restored: FullOrdIdMap<DefaultId, TestO, TestT> = bincode::deserialize(&bytes).expect("反序列化失败");
+        assert_eq!(restored.id_map.max_id(), full.id_map.max_id());
+    }
+    
+    /// 非自描述格式：postcard
+    #[test]
+    fn test_obj_allocator_serde_postcard() {
+        let original = sample();
+        let bytes = postcard::to_allocvec(&original).expect("序列化失败");
+        let deserialized: OrdIdMap<DefaultId, TestO, TestT> = postcard::from_bytes(&bytes).expect("反序列化失败");
+        assert_eq!(deserialized, original);
+        
+        let full = FullOrdIdMap(original);
+        let bytes = postcard::to_allocvec(&full).expect("序列化失败");
+        let restored: FullOrdIdMap<DefaultId, TestO, TestT> = postcard::from_bytes(&bytes).expect("反序列化失败");
+        assert_eq!(restored.id_map.max_id(), full.id_map.max_id());
+    }
+    
+    /// CBOR：结构体以字符串键的 map 表示
+    #[test]
+    fn test_obj_allocator_serde_cbor() {
+        let original = FullOrdIdMap(sample());
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&original, &mut bytes).expect("序列化失败");
+        let restored: FullOrdIdMap<DefaultId, TestO, TestT> = ciborium::from_reader(bytes.as_slice()).expect("反序列化失败");
+        assert_eq!(restored.0, original.0);
+        assert_eq!(restored.id_map.max_id(), original.id_map.max_id());
+    }
+    
+    /// MessagePack：结构体默认以数组表示，也可以用带字段名的 map
+    #[test]
+    fn test_obj_allocator_serde_msgpack() {
+        let original = FullOrdIdMap(sample());
+        for bytes in [
+            rmp_serde::to_vec(&original).expect("序列化失败"),
+            rmp_serde::to_vec_named(&original).expect("序列化失败"),
+        ] {
+            let restored: FullOrdIdMap<DefaultId, TestO, TestT> = rmp_serde::from_slice(&bytes).expect("反序列化失败");
+            assert_eq!(restored.0, original.0);
+            assert_eq!(restored.id_map.max_id(), original.id_map.max_id());
+        }
+    }
+    
+    /// 重复 Id 直接报错
+    #[test]
+    fn test_obj_allocator_serde_duplicate_id() {
+        let elements = vec![
+            Pair(DefaultId(1), TestO(10)),
+            Pair(DefaultId(1), TestO(20)),
+        ];
+        let collex = FieldCollex::with_elements(default_span(), default_unit(), elements).unwrap();
+        let json = serde_json::to_string(&collex).unwrap();
+        
+        let err = serde_json::from_str::<OrdIdMap<DefaultId, TestO, TestT>>(&json).unwrap_err();
+        assert!(err.to_string().contains("DefaultId(1)"));
+    }
+    
+    /// 越界元素给出结构化的错误
+    #[test]
+    fn test_obj_allocator_load_error() {
+        let json = serde_json::to_string(&sample()).unwrap();
+        let mut raw: UncheckedOrdIdMap<DefaultId, TestO, TestT> = serde_json::from_str(&json).unwrap();
+        raw.span = Span::new_finite(0u32, 20u32);
+        
+        match OrdIdMap::try_from(raw) {
+            Err(LoadError::OutOfSpan { id, value }) => {
+                assert_eq!(id, DefaultId(3));
+                assert_eq!(value, 30);
+            }
+            other => panic!("unexpected: {:?}", other.map(|_| ())),
+        }
+    }
+    
+    /// 元素借用输入缓冲区，不复制字符串
+    #[test]
+    fn test_obj_allocator_serde_borrowed() {
+        #[derive(Debug, serde::Serialize, Deserialize)]
+        struct Named<'a>(u32, &'a str);
+        impl Collexetable<TestT> for Named<'_> {
+            fn collexate(&self) -> TestT { self.0 }
+            
+            fn collexate_ref(&self) -> &TestT {
+                &self.0
+            }
+            
+            fn collexate_mut(&mut self) -> &mut TestT {
+                &mut self.0
+            }
+        }
+        
+        let mut original = OrdIdMap::new(default_span(), default_unit()).unwrap();
+        let id = original.insert(Named(10, "first")).unwrap();
+        let json = serde_json::to_string(&original).unwrap();
+        
+        let deserialized: OrdIdMap<DefaultId, Named<'_>, TestT> = serde_json::from_str(&json).unwrap();
+        let name = deserialized.get_with_id(id).unwrap().1;
+        assert_eq!(name, "first");
+        // 指向 json 缓冲区内部
+        assert!(json.as_bytes().as_ptr_range().contains(&name.as_ptr()));
+    }
+    
+    /// 由调用者提供 span 与 unit
+    #[test]
+    fn test_obj_allocator_seed() {
+        let json = serde_json::to_string(&sample()).unwrap();
+        
+        let seed = OrdIdMapSeed::<DefaultId, TestO, TestT>::new(Span::new_finite(0u32, 100u32), 10);
+        let map = seed.deserialize(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+        assert_eq!(map.span(), &Span::new_finite(0u32, 100u32));
+        assert_eq!(map.get_with_id(DefaultId(3)), Some(&TestO(30)));
+        
+        let seed = OrdIdMapSeed::<DefaultId, TestO, TestT>::new(Span::new_finite(0u32, 20u32), 10);
+        let err = seed.deserialize(&mut serde_json::Deserializer::from_str(&json)).unwrap_err();
+        assert!(err.to_string().contains("DefaultId(3)"));
+    }
+    
+    /// 旧存档迁移到新元素类型
+    #[test]
+    fn test_obj_allocator_upgrade() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct TestV2(u32, String);
+        impl Collexetable<TestT> for TestV2 {
+            fn collexate(&self) -> TestT { self.0 }
+            
+            fn collexate_ref(&self) -> &TestT {
+                &self.0
+            }
+            
+            fn collexate_mut(&mut self) -> &mut TestT {
+                &mut self.0
+            }
+        }
+        
+        let json = serde_json::to_string(&sample()).unwrap();
+        let upgraded: OrdIdMap<DefaultId, TestV2, TestT> = OrdIdMap::deserialize_with_upgrade(
+            &mut serde_json::Deserializer::from_str(&json),
+            |old: TestO| TestV2(old.0, format!("#{}", old.0)),
+        ).unwrap();
+        assert_eq!(upgraded.get_with_id(DefaultId(3)), Some(&TestV2(30, "#30".into())));
+        assert_eq!(upgraded.len(), 2);
+        
+        let err = OrdIdMap::<DefaultId, TestV2, TestT>::deserialize_with_upgrade(
+            &mut serde_json::Deserializer::from_str(&json),
+            |old: TestO| TestV2(old.0 + 1, String::new()),
+        ).unwrap_err();
+        assert!(err.to_string().contains("DefaultId(1)"));
+    }
+}
@@ -0,0 +1,95 @@
+//! 带上下文的错误：失败时带回 Id、field 值与 span，而不是 field_collex 的原始错误
+
+use std::cell::Cell;
+use field_collex::{Collexetable, FieldValue};
+use field_collex::collex::InsertFieldCollexError;
+use crate::{Id, InsertError, ModifyError, OrdIdMap, RemoveError};
+
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    /// 同 `insert`，失败时的错误带有本应分配的 Id、field 值与 span（或占据该位置的元素的 Id）
+    pub fn insert_detailed(&mut self, elem: E) -> Result<K, InsertError<K, E, V>> {
+        let id = self.peek_next_id().expect("id space exhausted");
+        let value = elem.collexate();
+        self.insert(elem).map_err(|err| self.insert_error(id, value, err))
+    }
+    
+    /// 同 `insert_with_id`，失败时的错误带有上下文
+    pub fn insert_with_id_detailed(&mut self, id: K, elem: E) -> Result<Option<E>, InsertError<K, E, V>> {
+        let value = elem.collexate();
+        self.insert_with_id(id, elem).map_err(|err| self.insert_error(id, value, err))
+    }
+    
+    /// 同 `try_modify`，失败时的错误带有修改前后的 field 值与 span
+    pub fn modify_detailed<F, R>(&mut self, id: K, f: F) -> Result<R, ModifyError<K, V, R>>
+    where
+        F: FnOnce(&mut E) -> R,
+    {
+        let Some(from) = self.locate(id) else {
+            return Err(ModifyError::CannotFind(id));
+        };
+        let attempted = Cell::new(None);
+        self.try_modify(id, |e| {
+            let r = f(e);
+            attempted.set(Some(e.collexate()));
+            r
+        }).map_err(|raw| match attempted.get() {
+            Some(to) => ModifyError::Rejected { id, from, to, span: self.collex.span().clone(), raw },
+            None => ModifyError::CannotFind(id),
+        })
+    }
+    
+    /// 同 `remove`，Id 不存在时区分已被删除与从未分配
+    pub fn remove_detailed(&mut self, id: K) -> Result<E, RemoveError<K>> {
+        self.remove(id).ok_or_else(|| {
+            if id.as_u64() <= self.id_map.max_id().as_u64() {
+                RemoveError::Stale(id)
+            } else {
+                RemoveError::NeverExisted(id)
+            }
+        })
+    }
+    
+    fn insert_error(&self, id: K, value: V, err: InsertFieldCollexError<E>) -> InsertError<K, E, V> {
+        match err {
+            InsertFieldCollexError::OutOfSpan(elem) => {
+                InsertError::OutOfSpan { id, value, span: self.collex.span().clone(), elem }
+            }
+            InsertFieldCollexError::AlreadyExist(elem) => {
+                let occupant = self.collex.get(value).map(|p| p.0);
+                InsertError::AlreadyExist { id, value, occupant, elem }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DefaultId, InsertError, ModifyError, RemoveError};
+    use crate::test_util::{TestO, new_map};
+    
+    #[test]
+    fn test_detailed_errors() {
+        let mut map = new_map();
+        let a = map.insert_detailed(TestO(10)).unwrap();
+        
+        let err = map.insert_detailed(TestO(10)).unwrap_err();
+        assert!(matches!(err, InsertError::AlreadyExist { value: 10, occupant: Some(id), .. } if id == a));
+        assert_eq!(err.into_elem(), TestO(10));
+        let err = map.insert_detailed(TestO(200)).unwrap_err();
+        assert!(matches!(err, InsertError::OutOfSpan { value: 200, .. }));
+        
+        let b = map.insert_detailed(TestO(20)).unwrap();
+        let err = map.modify_detailed(b, |e| e.0 = 10).unwrap_err();
+        assert!(matches!(err, ModifyError::Rejected { from: 20, to: 10, .. }));
+        assert!(matches!(map.modify_detailed(DefaultId(99), |_| ()), Err(ModifyError::CannotFind(_))));
+        
+        assert_eq!(map.remove_detailed(a), Ok(TestO(10)));
+        assert_eq!(map.remove_detailed(a), Err(RemoveError::Stale(a)));
+        assert_eq!(map.remove_detailed(DefaultId(99)), Err(RemoveError::NeverExisted(DefaultId(99))));
+    }
+}
@@ -0,0 +1,155 @@
+use field_collex::{Collexetable, FieldValue};
+use field_collex::collex::{InsertFieldCollexError, ModifyFieldCollexError};
+use crate::{Id, ModifyGuard, OrdIdMap};
+
+/// `OrdIdMap::entry` 返回的条目，collex 与 id_map 始终同步更新
+pub enum Entry<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    Occupied(OccupiedEntry<'a, K, E, V>),
+    Vacant(VacantEntry<'a, K, E, V>),
+}
+
+/// 已存在元素的条目
+pub struct OccupiedEntry<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    map: &'a mut OrdIdMap<K, E, V>,
+    id: K,
+}
+
+/// 尚无元素的条目
+pub struct VacantEntry<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    map: &'a mut OrdIdMap<K, E, V>,
+    id: K,
+}
+
+impl<'a, K, E, V> Entry<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    pub fn id(&self) -> K {
+        match self {
+            Entry::Occupied(e) => e.id,
+            Entry::Vacant(e) => e.id,
+        }
+    }
+    
+    /// 元素存在时就地修改；修改后越界或冲突的元素会退回原值
+    pub fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut E),
+    {
+        match self {
+            Entry::Occupied(e) => {
+                if let Some(mut guard) = e.map.get_mut_with_id(e.id) {
+                    f(&mut guard);
+                }
+                Entry::Occupied(e)
+            }
+            Entry::Vacant(e) => Entry::Vacant(e),
+        }
+    }
+    
+    /// 元素不存在时以该 Id 插入 `elem`，返回元素的可变访问守卫
+    pub fn or_insert(self, elem: E) -> Result<ModifyGuard<'a, K, E, V>, InsertFieldCollexError<E>> {
+        self.or_insert_with(|| elem)
+    }
+    
+    /// 元素不存在时以该 Id 插入 `f()` 的结果，返回元素的可变访问守卫
+    pub fn or_insert_with<F>(self, f: F) -> Result<ModifyGuard<'a, K, E, V>, InsertFieldCollexError<E>>
+    where
+        F: FnOnce() -> E,
+    {
+        match self {
+            Entry::Occupied(e) => Ok(e.into_mut()),
+            Entry::Vacant(e) => e.insert(f()),
+        }
+    }
+    
+    /// 删除元素（若存在）
+    pub fn remove(self) -> Option<E> {
+        match self {
+            Entry::Occupied(e) => Some(e.remove()),
+            Entry::Vacant(_) => None,
+        }
+    }
+}
+
+impl<'a, K, E, V> OccupiedEntry<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    pub fn id(&self) -> K {
+        self.id
+    }
+    
+    pub fn get(&self) -> &E {
+        self.map.get_with_id(self.id).expect("occupied entry")
+    }
+    
+    /// 转为元素的可变访问守卫
+    pub fn into_mut(self) -> ModifyGuard<'a, K, E, V> {
+        self.map.get_mut_with_id(self.id).expect("occupied entry")
+    }
+    
+    pub fn modify<F,R>(&mut self, f: F) -> Result<R, ModifyFieldCollexError<(R,E)>>
+    where
+        F: FnOnce(&mut E) -> R,
+    {
+        self.map.modify(self.id, f)
+    }
+    
+    pub fn remove(self) -> E {
+        self.map.remove(self.id).expect("occupied entry")
+    }
+}
+
+impl<'a, K, E, V> VacantEntry<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    pub fn id(&self) -> K {
+        self.id
+    }
+    
+    /// 以该 Id 插入元素，返回元素的可变访问守卫
+    pub fn insert(self, elem: E) -> Result<ModifyGuard<'a, K, E, V>, InsertFieldCollexError<E>> {
+        self.map.insert_with_id(self.id, elem)?;
+        Ok(self.map.get_mut_with_id(self.id).expect("just inserted"))
+    }
+}
+
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    /// 获取 Id 对应的条目
+    pub fn entry(&mut self, id: K) -> Entry<'_, K, E, V> {
+        self.ensure_index();
+        if self.get_with_id(id).is_some() {
+            Entry::Occupied(OccupiedEntry { map: self, id })
+        } else {
+            Entry::Vacant(VacantEntry { map: self, id })
+        }
+    }
+}
@@ -0,0 +1,350 @@
+use std::fmt;
+use field_collex::collex::{
+    InsertFieldCollexError, ModifyFieldCollexError, NewFieldCollexError, WithCapacityFieldCollexError,
+    WithElementsFieldCollexError,
+};
+use span_core::Span;
+
+/// `replace` 的错误，均带回未能放入的新元素
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplaceError<E> {
+    /// Id 不存在
+    CannotFind(E),
+    /// 新元素的 field 值超出 span
+    OutOfSpan(E),
+    /// 新元素的 field 值已被其他元素占用
+    AlreadyExist(E),
+}
+
+/// `swap` 的错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapError<K> {
+    /// Id 不存在
+    CannotFind(K),
+}
+
+/// `modify_many` 中单个 Id 的失败原因
+///
+/// 除 `CannotFind` 外，对应元素均已退回修改前的值
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModifyManyError {
+    /// Id 不存在
+    CannotFind,
+    /// 修改后的 field 值超出 span
+    OutOfSpan,
+    /// 修改后的 field 值与其他元素冲突
+    AlreadyExist,
+}
+
+/// 按 Id 查询失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdError<K> {
+    /// Id 曾经存在，但已被删除（过期句柄）
+    Stale(K),
+    /// Id 从未被分配过
+    NeverExisted(K),
+}
+
+impl<K: fmt::Debug> fmt::Display for IdError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdError::Stale(id) => write!(f, "Id {:?} 已被删除", id),
+            IdError::NeverExisted(id) => write!(f, "Id {:?} 从未被分配", id),
+        }
+    }
+}
+
+impl<K: fmt::Debug> std::error::Error for IdError<K> {}
+
+/// `remove_detailed` 的错误：Id 已被删除或从未被分配
+///
+/// 删除只会因 Id 不存在而失败，此时没有对应的元素，也就没有 field 值或 span 可以附带，只需区分两种原因
+pub type RemoveError<K> = IdError<K>;
+
+/// `insert_detailed` 与 `insert_with_id_detailed` 的错误，带回元素与出错时的上下文
+#[derive(Debug, Clone, PartialEq)]
+pub enum InsertError<K, E, V> {
+    /// 元素的 field 值超出 span
+    OutOfSpan { id: K, value: V, span: Span<V>, elem: E },
+    /// 元素的 field 值已被其他元素占用，`occupant` 为占据该位置的元素的 Id（若能确定）
+    AlreadyExist { id: K, value: V, occupant: Option<K>, elem: E },
+}
+
+impl<K, E, V> InsertError<K, E, V> {
+    /// 插入时使用（或本应分配）的 Id
+    pub fn id(&self) -> &K {
+        match self {
+            InsertError::OutOfSpan { id, .. } | InsertError::AlreadyExist { id, .. } => id,
+        }
+    }
+    
+    /// 取回未能插入的元素
+    pub fn into_elem(self) -> E {
+        match self {
+            InsertError::OutOfSpan { elem, .. } | InsertError::AlreadyExist { elem, .. } => elem,
+        }
+    }
+}
+
+impl<K, E, V> From<InsertError<K, E, V>> for InsertFieldCollexError<E> {
+    fn from(err: InsertError<K, E, V>) -> Self {
+        match err {
+            InsertError::OutOfSpan { elem, .. } => InsertFieldCollexError::OutOfSpan(elem),
+            InsertError::AlreadyExist { elem, .. } => InsertFieldCollexError::AlreadyExist(elem),
+        }
+    }
+}
+
+impl<K: fmt::Debug, E, V: fmt::Debug> fmt::Display for InsertError<K, E, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InsertError::OutOfSpan { id, value, span, .. } => {
+                write!(f, "元素 {:?} 的 field 值 {:?} 超出 span {:?}", id, value, span)
+            }
+            InsertError::AlreadyExist { id, value, occupant: Some(occupant), .. } => {
+                write!(f, "元素 {:?} 的 field 值 {:?} 与元素 {:?} 冲突", id, value, occupant)
+            }
+            InsertError::AlreadyExist { id, value, occupant: None, .. } => {
+                write!(f, "元素 {:?} 的 field 值 {:?} 与已有元素冲突", id, value)
+            }
+        }
+    }
+}
+
+impl<K: fmt::Debug, E: fmt::Debug, V: fmt::Debug> std::error::Error for InsertError<K, E, V> {}
+
+/// `modify_detailed` 的错误
+#[derive(Debug)]
+pub enum ModifyError<K, V, R> {
+    /// Id 不存在
+    CannotFind(K),
+    /// 修改后的 field 值无法放回（超出 span 或与其他元素冲突），`raw` 为 field_collex 的原始错误，其中带回闭包的返回值
+    Rejected { id: K, from: V, to: V, span: Span<V>, raw: ModifyFieldCollexError<R> },
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, R> fmt::Display for ModifyError<K, V, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModifyError::CannotFind(id) => write!(f, "Id {:?} 不存在", id),
+            ModifyError::Rejected { id, from, to, span, .. } => {
+                write!(f, "元素 {:?} 的 field 值无法从 {:?} 改为 {:?}（span 为 {:?}）", id, from, to, span)
+            }
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, R: fmt::Debug> std::error::Error for ModifyError<K, V, R> {}
+
+/// id_map 与 collex 不一致：id_map 记录 `id` 位于 `value`，但 collex 在该位置没有这个 Id 的元素
+///
+/// 通常由绕过 OrdIdMap 直接修改 collex 引起，可调用 `rebuild_index` 修复
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InconsistentState<K, V> {
+    pub id: K,
+    pub value: V,
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Display for InconsistentState<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "id_map 与 collex 不一致: Id {:?} 记录的位置 {:?} 上没有该元素", self.id, self.value)
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> std::error::Error for InconsistentState<K, V> {}
+
+/// `resize_span` 的错误
+#[derive(Debug)]
+pub enum ResizeSpanError<K, V> {
+    /// 新 span 与 unit 无法构成合法的 FieldCollex
+    Invalid(NewFieldCollexError<V>),
+    /// 这些元素的 field 值无法放入新 span
+    OutOfSpan(Vec<(K, V)>),
+}
+
+/// `set_unit` 的错误
+#[derive(Debug)]
+pub enum SetUnitError<K, V> {
+    /// span 与新 unit 无法构成合法的 FieldCollex
+    Invalid(NewFieldCollexError<V>),
+    /// 这些元素在新 unit 下无法放入（越界或彼此冲突）
+    Violations(Vec<(K, V)>),
+}
+
+/// id_map 与 collex 之间的不一致
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConsistencyError<K, V> {
+    /// id_map 记录的位置上没有元素
+    Missing { id: K, value: V },
+    /// id_map 记录的位置上是另一个 Id 的元素
+    WrongId { id: K, value: V, found: K },
+    /// id_map 记录的 field 值与元素实际的 field 值不同
+    ValueMismatch { id: K, recorded: V, actual: V },
+    /// collex 中的元素在 id_map 中没有记录
+    Orphan { id: K, value: V },
+    /// collex 中有多个元素使用同一个 Id
+    DuplicateId { id: K, value: V },
+}
+
+/// `CursorMut::insert_after` 的错误，均带回未能插入的元素
+#[derive(Debug, Clone, PartialEq)]
+pub enum InsertAfterError<E> {
+    /// 元素的 field 值不在当前元素与下一个元素之间
+    OutOfOrder(E),
+    /// 元素的 field 值超出 span
+    OutOfSpan(E),
+    /// 元素的 field 值已被其他元素占用
+    AlreadyExist(E),
+}
+
+/// `alloc_run` 的错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocRunError<V> {
+    /// span 无界，无法划分槽位
+    Unbounded,
+    /// 找不到这么多个相邻的空闲槽位
+    NoRoom(usize),
+    /// 第 `index` 个元素的 field 值不在分配给它的槽位内，所有元素均未插入
+    Misplaced { index: usize, slot: V, value: V },
+}
+
+/// `insert_auto` 的错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum InsertAutoError<E> {
+    /// span 无界，无法划分槽位
+    Unbounded,
+    /// 没有空闲槽位
+    NoRoom,
+    /// 构造出的元素的 field 值超出 span
+    OutOfSpan(E),
+    /// 构造出的元素的 field 值已被其他元素占用
+    AlreadyExist(E),
+}
+
+/// `ShardedOrdIdMap::new` 与 `CowOrdIdMap::new` 的错误
+#[derive(Debug)]
+pub enum NewShardedError<V> {
+    /// span 无界，无法分片
+    Unbounded,
+    /// 分片数为 0
+    NoShards,
+    /// span 与 unit 无法构成合法的 FieldCollex
+    Invalid(NewFieldCollexError<V>),
+}
+
+/// `OrdIdMapBuilder::build` 的错误
+#[derive(Debug)]
+pub enum BuildError<V> {
+    /// 未设置 span
+    MissingSpan,
+    /// 未设置 unit
+    MissingUnit,
+    /// span 与 unit 无法构成合法的 FieldCollex
+    Invalid(NewFieldCollexError<V>),
+    /// 按容量构造 FieldCollex 失败
+    WithCapacity(WithCapacityFieldCollexError<V>),
+    /// 以初始元素构造 FieldCollex 失败
+    WithElements(WithElementsFieldCollexError<V>),
+}
+
+/// actor 已停止，命令未被处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActorStopped;
+
+impl fmt::Display for ActorStopped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "actor 已停止")
+    }
+}
+
+impl std::error::Error for ActorStopped {}
+
+/// 由未经校验的内容构建 OrdIdMap（如反序列化）时的错误，指明出错的元素
+#[derive(Debug)]
+pub enum LoadError<K, V> {
+    /// span 与 unit 无法构成合法的 FieldCollex
+    Invalid(NewFieldCollexError<V>),
+    /// 多个元素使用同一个 Id
+    DuplicateId(K),
+    /// 元素的 field 值超出 span
+    OutOfSpan { id: K, value: V },
+    /// 元素的 field 值与已放入的元素冲突，`occupant` 为占据该位置的元素的 Id（若能确定）
+    AlreadyExist { id: K, value: V, occupant: Option<K> },
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Display for LoadError<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Invalid(err) => write!(f, "span 与 unit 不合法: {:?}", err),
+            LoadError::DuplicateId(id) => write!(f, "元素 Id 重复: {:?}", id),
+            LoadError::OutOfSpan { id, value } => write!(f, "元素 {:?} 的 field 值 {:?} 超出 span", id, value),
+            LoadError::AlreadyExist { id, value, occupant: Some(occupant) } => {
+                write!(f, "元素 {:?} 的 field 值 {:?} 与元素 {:?} 冲突", id, value, occupant)
+            }
+            LoadError::AlreadyExist { id, value, occupant: None } => {
+                write!(f, "元素 {:?} 的 field 值 {:?} 与已有元素冲突", id, value)
+            }
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> std::error::Error for LoadError<K, V> {}
+
+/// `import_with_ids` 中单个 `(Id, 元素)` 被拒绝的原因，均带回该元素
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportRejection<E> {
+    /// Id 已存在（包括与同一批中较早的元素重复）
+    DuplicateId(E),
+    /// 元素的 field 值超出 span
+    OutOfSpan(E),
+    /// 元素的 field 值已被其他元素占用
+    AlreadyExist(E),
+}
+
+/// `import_with_ids` 的错误：被拒绝的 `(Id, 原因)`，按传入顺序排列；其余元素均已导入
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportError<K, E> {
+    pub rejected: Vec<(K, ImportRejection<E>)>,
+}
+
+/// 读写文件的错误，区分 IO 错误与格式错误
+#[derive(Debug)]
+pub enum PersistError {
+    Io(std::io::Error),
+    Format(serde_json::Error),
+    /// 校验和不符（或数据短于校验和本身），内容已损坏
+    CorruptSnapshot { expected: u32, actual: u32 },
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistError::Io(err) => write!(f, "IO 错误: {}", err),
+            PersistError::Format(err) => write!(f, "格式错误: {}", err),
+            PersistError::CorruptSnapshot { expected, actual } => {
+                write!(f, "快照已损坏: 校验和应为 {:#010x}，实际为 {:#010x}", expected, actual)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PersistError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PersistError::Io(err) => Some(err),
+            PersistError::Format(err) => Some(err),
+            PersistError::CorruptSnapshot { .. } => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PersistError {
+    fn from(err: std::io::Error) -> Self {
+        PersistError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for PersistError {
+    fn from(err: serde_json::Error) -> Self {
+        PersistError::Format(err)
+    }
+}
@@ -0,0 +1,100 @@
+//! 表格导出：每个元素一行 `(id, field 值, 元素)`，便于导入 pandas / duckdb 等工具
+
+use std::io::{self, Write};
+use field_collex::{Collexetable, FieldValue};
+use serde::Serialize;
+use crate::{Id, OrdIdMap};
+
+#[derive(Serialize)]
+struct Row<'a, V, E> {
+    id: u64,
+    value: V,
+    element: &'a E,
+}
+
+/// CSV 字段转义：含逗号、引号或换行时整体加引号，内部引号加倍
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V> + Serialize,
+    V: FieldValue + Serialize,
+{
+    /// 按 field 顺序导出为 JSON Lines，每行 `{"id": .., "value": .., "element": ..}`
+    pub fn export_jsonl<W: Write>(&self, mut w: W) -> io::Result<()> {
+        for p in self.collex.iter() {
+            let row = Row { id: p.0.as_u64(), value: p.collexate(), element: &p.1 };
+            serde_json::to_writer(&mut w, &row)?;
+            w.write_all(b"\n")?;
+        }
+        w.flush()
+    }
+    
+    /// 按 field 顺序导出为 CSV，表头 `id,value,element`；field 值与元素以 JSON 文本写入
+    pub fn export_csv<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, "id,value,element")?;
+        for p in self.collex.iter() {
+            let value = serde_json::to_string(p.collexate_ref())?;
+            let element = serde_json::to_string(&p.1)?;
+            writeln!(w, "{},{},{}", p.0.as_u64(), csv_field(&value), csv_field(&element))?;
+        }
+        w.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use field_collex::Collexetable;
+    use serde::Serialize;
+    use span_core::Span;
+    use crate::{DefaultId, OrdIdMap};
+    
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    struct TestO(u32, &'static str);
+    impl Collexetable<u32> for TestO {
+        fn collexate(&self) -> u32 { self.0 }
+        
+        fn collexate_ref(&self) -> &u32 {
+            &self.0
+        }
+        
+        fn collexate_mut(&mut self) -> &mut u32 {
+            &mut self.0
+        }
+    }
+    
+    fn new_map() -> OrdIdMap<DefaultId, TestO, u32> {
+        let mut map = OrdIdMap::new(Span::new_finite(0u32, 100u32), 10).unwrap();
+        map.insert(TestO(20, "b")).unwrap();
+        map.insert(TestO(10, "a")).unwrap();
+        map
+    }
+    
+    #[test]
+    fn test_export_jsonl() {
+        let mut out = Vec::new();
+        new_map().export_jsonl(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), concat!(
+            r#"{"id":2,"value":10,"element":[10,"a"]}"#, "\n",
+            r#"{"id":1,"value":20,"element":[20,"b"]}"#, "\n",
+        ));
+    }
+    
+    #[test]
+    fn test_export_csv() {
+        let mut out = Vec::new();
+        new_map().export_csv(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), concat!(
+            "id,value,element\n",
+            "2,10,\"[10,\"\"a\"\"]\"\n",
+            "1,20,\"[20,\"\"b\"\"]\"\n",
+        ));
+    }
+}
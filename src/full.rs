@@ -0,0 +1,225 @@
+//! 完整状态的序列化：在元素之外一并保存 max_id
+//!
+//! OrdIdMap 本身只序列化 collex，反序列化后 max_id 取现存元素中最大的 Id；
+//! 若保存前删除过 Id 最大的元素，之后分配的新 Id 会与旧 Id 重复。需要精确往返时使用 `FullOrdIdMap`
+//!
+//! 完整格式的第一个字段是格式版本号，反序列化按版本号分派，今后调整布局时旧存档仍可读取
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use field_collex::{Collexetable, FieldValue};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::{Error, MapAccess, SeqAccess, Visitor};
+use crate::{Id, OrdIdMap};
+
+/// 按完整状态（含 max_id）序列化的 OrdIdMap
+#[derive(Debug, Clone)]
+pub struct FullOrdIdMap<K, E, V>(pub OrdIdMap<K, E, V>)
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue;
+
+/// 当前写出的完整格式版本号
+pub const FORMAT_VERSION: u32 = 1;
+
+const FIELDS: &[&str] = &["version", "max_id", "collex"];
+
+#[derive(Serialize)]
+#[serde(rename = "OrdIdMap")]
+struct FullRef<'a, K, E, V>
+where
+    K: Id + Serialize,
+    E: Collexetable<V> + Serialize,
+    V: FieldValue + Serialize,
+{
+    version: u32,
+    max_id: K,
+    collex: &'a OrdIdMap<K, E, V>,
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum Field {
+    Version,
+    MaxId,
+    Collex,
+}
+
+struct FullVisitor<K, E, V>(PhantomData<(K, E, V)>);
+
+impl<'de, K, E, V> Visitor<'de> for FullVisitor<K, E, V>
+where
+    K: Id + Deserialize<'de>,
+    E: Collexetable<V> + Deserialize<'de>,
+    V: FieldValue + fmt::Debug + Deserialize<'de>,
+{
+    type Value = FullOrdIdMap<K, E, V>;
+    
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("带版本号的 OrdIdMap 完整状态")
+    }
+    
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let version: u32 = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(0, &self))?;
+        match version {
+            1 => {
+                let max_id = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(1, &self))?;
+                let collex = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(2, &self))?;
+                Ok(FullOrdIdMap::from_parts_v1(max_id, collex))
+            }
+            v => Err(A::Error::custom(format!("不支持的格式版本: {}", v))),
+        }
+    }
+    
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        // 版本号决定其余字段的含义，因此必须最先出现
+        let Some(Field::Version) = map.next_key()? else {
+            return Err(A::Error::custom("格式版本号必须是第一个字段"));
+        };
+        let version: u32 = map.next_value()?;
+        match version {
+            1 => {
+                let (mut max_id, mut collex) = (None, None);
+                while let Some(key) = map.next_key()? {
+                    match key {
+                        Field::Version => return Err(A::Error::duplicate_field("version")),
+                        Field::MaxId => max_id = Some(map.next_value()?),
+                        Field::Collex => collex = Some(map.next_value()?),
+                    }
+                }
+                let max_id = max_id.ok_or_else(|| A::Error::missing_field("max_id"))?;
+                let collex = collex.ok_or_else(|| A::Error::missing_field("collex"))?;
+                Ok(FullOrdIdMap::from_parts_v1(max_id, collex))
+            }
+            v => Err(A::Error::custom(format!("不支持的格式版本: {}", v))),
+        }
+    }
+}
+
+impl<K, E, V> FullOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    pub fn into_inner(self) -> OrdIdMap<K, E, V> {
+        self.0
+    }
+    
+    fn from_parts_v1(max_id: K, mut map: OrdIdMap<K, E, V>) -> Self {
+        map.id_map.bump_max_id(max_id);
+        Self(map)
+    }
+}
+
+impl<K, E, V> From<OrdIdMap<K, E, V>> for FullOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    fn from(map: OrdIdMap<K, E, V>) -> Self {
+        Self(map)
+    }
+}
+
+impl<K, E, V> Deref for FullOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    type Target = OrdIdMap<K, E, V>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<K, E, V> DerefMut for FullOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<K, E, V> Serialize for FullOrdIdMap<K, E, V>
+where
+    K: Id + Serialize,
+    E: Collexetable<V> + Serialize,
+    V: FieldValue + Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        as_full(&self.0).serialize(serializer)
+    }
+}
+
+/// 以完整格式序列化 `map`，无需先转移所有权包装成 FullOrdIdMap
+pub(crate) fn as_full<K, E, V>(map: &OrdIdMap<K, E, V>) -> impl Serialize + '_
+where
+    K: Id + Serialize,
+    E: Collexetable<V> + Serialize,
+    V: FieldValue + Serialize,
+{
+    FullRef {
+        version: FORMAT_VERSION,
+        max_id: map.id_map.max_id(),
+        collex: map,
+    }
+}
+
+impl<'de, K, E, V> Deserialize<'de> for FullOrdIdMap<K, E, V>
+where
+    K: Id + Deserialize<'de>,
+    E: Collexetable<V> + Deserialize<'de>,
+    V: FieldValue + fmt::Debug + Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct("OrdIdMap", FIELDS, FullVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::DefaultId;
+    use super::FullOrdIdMap;
+    use crate::test_util::{TestO, new_map};
+    
+    #[test]
+    fn test_full_state_keeps_max_id() {
+        let mut map = new_map();
+        map.insert(TestO(10)).unwrap();
+        let last = map.insert(TestO(20)).unwrap();
+        map.remove(last);
+        
+        let json = serde_json::to_string(&FullOrdIdMap(map)).unwrap();
+        let mut restored: FullOrdIdMap<DefaultId, TestO, u32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.id_map.max_id(), last);
+        let next = restored.insert(TestO(30)).unwrap();
+        assert_ne!(next, last);
+    }
+    
+    #[test]
+    fn test_unknown_format_version() {
+        let json = r#"{"version":99,"max_id":1,"collex":null}"#;
+        let err = serde_json::from_str::<FullOrdIdMap<DefaultId, TestO, u32>>(json).unwrap_err();
+        assert!(err.to_string().contains("99"));
+    }
+}
@@ -0,0 +1,294 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+use field_collex::{Collexetable, FieldCollex, FieldValue};
+use field_collex::collex::InsertFieldCollexError;
+use crate::{ChangeKind, Id, ModifyManyError, OrdIdMap, Pair};
+
+/// 暂存被取出的元素，在最后一个守卫释放时统一写回 collex 与 id_map
+pub(crate) struct WriteBack<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    pub(crate) map: &'a mut OrdIdMap<K, E, V>,
+    pending: Vec<(V, Pair<K, E>)>,
+}
+
+impl<'a, K, E, V> WriteBack<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    pub(crate) fn new(map: &'a mut OrdIdMap<K, E, V>) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            map,
+            pending: Vec::new(),
+        }))
+    }
+}
+
+impl<'a, K, E, V> Drop for WriteBack<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    fn drop(&mut self) {
+        let pending = std::mem::take(&mut self.pending);
+        write_back(self.map, pending);
+    }
+}
+
+/// 将取出的元素按新值写回 collex 与 id_map，`pending` 中为 `(原值, 元素)`
+///
+/// 新值越界或冲突的元素退回原值；原值若被其他改动过的元素占据，则连同其一并退回。
+/// 返回所有被退回的 Id 及原因。
+pub(crate) fn write_back<K, E, V>(
+    map: &mut OrdIdMap<K, E, V>,
+    pending: Vec<(V, Pair<K, E>)>,
+) -> Vec<(K, ModifyManyError)>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    use InsertFieldCollexError::*;
+    let olds: HashMap<u64, V> = pending
+        .iter()
+        .map(|(old, pair)| (pair.0.as_u64(), *old))
+        .collect();
+    
+    // 未改动的元素先归位，其原位不可能被占用
+    let (kept, moved): (Vec<_>, Vec<_>) = pending
+        .into_iter()
+        .partition(|(old, pair)| pair.collexate() == *old);
+    let mut queue: VecDeque<Pair<K, E>> = kept
+        .into_iter()
+        .chain(moved)
+        .map(|(_, pair)| pair)
+        .collect();
+    
+    let mut rejected = Vec::new();
+    while let Some(pair) = queue.pop_front() {
+        let id = pair.0;
+        let value = pair.collexate();
+        match map.collex.insert(pair) {
+            Ok(_) => {
+                map.set_index(id, value);
+                if value != olds[&id.as_u64()] {
+                    map.changes.record(id, ChangeKind::Modified);
+                }
+            }
+            Err(err) => {
+                let (reason, mut pair) = match err {
+                    OutOfSpan(pair) => (ModifyManyError::OutOfSpan, pair),
+                    AlreadyExist(pair) => (ModifyManyError::AlreadyExist, pair),
+                };
+                let old = olds[&id.as_u64()];
+                if value != old {
+                    *pair.collexate_mut() = old;
+                    rejected.push((id, reason));
+                }
+                if let Ok(occupant) = map.collex.remove(old) {
+                    queue.push_back(occupant);
+                }
+                queue.push_front(pair);
+            }
+        }
+    }
+    map.debug_check_invariants(None);
+    rejected
+}
+
+/// 底层 collex 的可变访问守卫
+///
+/// 释放时按 collex 中元素自带的 Id 重建 id_map，并把前后差异记入变更记录
+pub struct CollexGuard<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    map: &'a mut OrdIdMap<K, E, V>,
+}
+
+impl<'a, K, E, V> CollexGuard<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    pub(crate) fn new(map: &'a mut OrdIdMap<K, E, V>) -> Self {
+        Self { map }
+    }
+}
+
+impl<'a, K, E, V> Deref for CollexGuard<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    type Target = FieldCollex<Pair<K, E>, V>;
+    fn deref(&self) -> &Self::Target {
+        &self.map.collex
+    }
+}
+
+impl<'a, K, E, V> DerefMut for CollexGuard<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.map.collex
+    }
+}
+
+impl<'a, K, E, V> Drop for CollexGuard<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    fn drop(&mut self) {
+        let before: HashMap<u64, V> = self.map.id_map
+            .iter()
+            .map(|(id, v)| (id.as_u64(), *v))
+            .collect();
+        self.map.rebuild_index();
+        
+        let map = &mut *self.map;
+        for (id, v) in map.id_map.iter() {
+            match before.get(&id.as_u64()) {
+                None => map.changes.record(id, ChangeKind::Inserted),
+                Some(old) if old != v => map.changes.record(id, ChangeKind::Modified),
+                Some(_) => {}
+            }
+        }
+        for &id in before.keys() {
+            let id = K::from_u64(id);
+            if !map.id_map.contains_id(id) {
+                map.changes.record(id, ChangeKind::Removed);
+            }
+        }
+    }
+}
+
+/// 元素的可变访问守卫
+///
+/// 元素在守卫存活期间被移出 collex，释放时重新 collexate 并写回 collex 与 id_map。
+/// 若新值越界或与其他元素冲突，该元素会退回原值。
+///
+/// 守卫被泄漏（如 `mem::forget`）时不会写回：元素随之丢失，而 id_map 仍保留其条目。
+/// 此后 `contains_id` 返回 false，`try_get_with_id` 返回 `InconsistentState`，
+/// `validate` 报告 `Missing`；调用 `rebuild_index` 丢弃该条目即可恢复一致，旧 Id 不会被复用。
+pub struct ModifyGuard<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    old: V,
+    pair: Option<Pair<K, E>>,
+    write_back: Rc<RefCell<WriteBack<'a, K, E, V>>>,
+}
+
+impl<'a, K, E, V> ModifyGuard<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    pub(crate) fn new(
+        pair: Pair<K, E>,
+        old: V,
+        write_back: Rc<RefCell<WriteBack<'a, K, E, V>>>,
+    ) -> Self {
+        Self {
+            old,
+            pair: Some(pair),
+            write_back,
+        }
+    }
+
+    /// 被守卫元素的 Id
+    pub fn id(&self) -> K {
+        self.pair.as_ref().unwrap().0
+    }
+}
+
+impl<'a, K, E, V> Deref for ModifyGuard<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    type Target = E;
+    fn deref(&self) -> &Self::Target {
+        &self.pair.as_ref().unwrap().1
+    }
+}
+
+impl<'a, K, E, V> DerefMut for ModifyGuard<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.pair.as_mut().unwrap().1
+    }
+}
+
+impl<'a, K, E, V> Drop for ModifyGuard<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    fn drop(&mut self) {
+        if let Some(pair) = self.pair.take() {
+            self.write_back.borrow_mut().pending.push((self.old, pair));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ConsistencyError, DefaultId, InconsistentState};
+    use crate::test_util::{TestO, new_map};
+    
+    #[test]
+    fn test_get_mut_with_id() {
+        let mut map = new_map();
+        let a = map.insert(TestO(10)).unwrap();
+        
+        map.get_mut_with_id(a).unwrap().0 = 30;
+        assert_eq!(map.get_with_id(a), Some(&TestO(30)));
+        assert_eq!(map.id_map[a], 30);
+        assert!(map.get(10).is_none());
+    }
+    
+    #[test]
+    fn test_leaked_guard() {
+        let mut map = new_map();
+        let a = map.insert(TestO(10)).unwrap();
+        let b = map.insert(TestO(20)).unwrap();
+        
+        std::mem::forget(map.get_mut_with_id(a).unwrap());
+        assert!(!map.contains_id(a));
+        assert_eq!(map.try_get_with_id(a), Err(InconsistentState { id: a, value: 10 }));
+        assert_eq!(map.validate(), Err(vec![ConsistencyError::Missing { id: a, value: 10 }]));
+        
+        map.rebuild_index();
+        assert!(map.validate().is_ok());
+        assert_eq!(map.ids().collect::<Vec<_>>(), vec![b]);
+        assert_eq!(map.insert(TestO(10)).unwrap(), DefaultId(3));
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::VecDeque;
+use std::ops::Deref;
+use field_collex::{Collexetable, FieldValue};
+use field_collex::collex::{InsertFieldCollexError, ModifyFieldCollexError};
+use crate::{Id, OrdIdMap};
+
+/// 一次可逆的修改
+#[derive(Debug, Clone)]
+enum Command<K, E> {
+    Insert { id: K, elem: E },
+    Remove { id: K, elem: E },
+    Modify { id: K, before: E, after: E },
+}
+
+/// 记录操作历史的 OrdIdMap，支持 undo / redo
+///
+/// 只读访问通过 Deref 直接使用内部的 OrdIdMap；写操作必须经由本类型以便记录
+#[derive(Debug)]
+pub struct TrackedOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V> + Clone,
+    V: FieldValue,
+{
+    map: OrdIdMap<K, E, V>,
+    undo: VecDeque<Command<K, E>>,
+    redo: Vec<Command<K, E>>,
+    depth: usize,
+}
+
+impl<K, E, V> Deref for TrackedOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V> + Clone,
+    V: FieldValue,
+{
+    type Target = OrdIdMap<K, E, V>;
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+impl<K, E, V> TrackedOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V> + Clone,
+    V: FieldValue,
+{
+    /// 包装已有的 OrdIdMap，最多保留 `depth` 步历史
+    pub fn new(map: OrdIdMap<K, E, V>, depth: usize) -> Self {
+        Self {
+            map,
+            undo: VecDeque::with_capacity(depth),
+            redo: Vec::new(),
+            depth,
+        }
+    }
+    
+    pub fn into_inner(self) -> OrdIdMap<K, E, V> {
+        self.map
+    }
+    
+    pub fn insert(&mut self, elem: E) -> Result<K, InsertFieldCollexError<E>> {
+        let copy = elem.clone();
+        let id = self.map.insert(elem)?;
+        self.record(Command::Insert { id, elem: copy });
+        Ok(id)
+    }
+    
+    pub fn remove(&mut self, id: K) -> Option<E> {
+        let elem = self.map.remove(id)?;
+        self.record(Command::Remove { id, elem: elem.clone() });
+        Some(elem)
+    }
+    
+    pub fn modify<F,R>(&mut self, id: K, f: F) -> Result<R, ModifyFieldCollexError<(R,E)>>
+    where
+        F: FnOnce(&mut E) -> R,
+    {
+        let before = self.map.get_with_id(id).cloned();
+        let result = self.map.modify(id, f);
+        match (before, self.map.get_with_id(id).cloned()) {
+            (Some(before), Some(after)) => self.record(Command::Modify { id, before, after }),
+            // 修改冲突时元素会被移出，按删除记录
+            (Some(before), None) => self.record(Command::Remove { id, elem: before }),
+            _ => {}
+        }
+        result
+    }
+    
+    /// 撤销一步，没有可撤销的操作时返回 false
+    pub fn undo(&mut self) -> bool {
+        let Some(cmd) = self.undo.pop_back() else {
+            return false;
+        };
+        match &cmd {
+            Command::Insert { id, .. } => {
+                self.map.remove(*id);
+            }
+            Command::Remove { id, elem } => {
+                let _ = self.map.insert_with_id(*id, elem.clone());
+            }
+            Command::Modify { id, before, .. } => {
+                let _ = self.map.replace(*id, before.clone());
+            }
+        }
+        self.redo.push(cmd);
+        true
+    }
+    
+    /// 重做一步，没有可重做的操作时返回 false
+    pub fn redo(&mut self) -> bool {
+        let Some(cmd) = self.redo.pop() else {
+            return false;
+        };
+        match &cmd {
+            Command::Insert { id, elem } => {
+                let _ = self.map.insert_with_id(*id, elem.clone());
+            }
+            Command::Remove { id, .. } => {
+                self.map.remove(*id);
+            }
+            Command::Modify { id, after, .. } => {
+                let _ = self.map.replace(*id, after.clone());
+            }
+        }
+        self.undo.push_back(cmd);
+        true
+    }
+    
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+    
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+    
+    /// 清空所有历史
+    pub fn clear_history(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+    
+    fn record(&mut self, cmd: Command<K, E>) {
+        self.redo.clear();
+        if self.depth == 0 {
+            return;
+        }
+        if self.undo.len() == self.depth {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(cmd);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TrackedOrdIdMap;
+    use crate::test_util::{TestO, new_map};
+    
+    #[test]
+    fn test_undo_redo() {
+        let map = new_map();
+        let mut tracked = TrackedOrdIdMap::new(map, 8);
+        
+        let a = tracked.insert(TestO(10)).unwrap();
+        tracked.modify(a, |e| e.0 = 40).unwrap();
+        tracked.remove(a);
+        assert!(!tracked.contains_id(a));
+        
+        assert!(tracked.undo());
+        assert_eq!(tracked.get_with_id(a), Some(&TestO(40)));
+        assert!(tracked.undo());
+        assert_eq!(tracked.get_with_id(a), Some(&TestO(10)));
+        assert!(tracked.undo());
+        assert!(!tracked.contains_id(a));
+        assert!(!tracked.undo());
+        
+        assert!(tracked.redo());
+        assert!(tracked.redo());
+        assert_eq!(tracked.get_with_id(a), Some(&TestO(40)));
+    }
+    
+    #[test]
+    fn test_history_depth() {
+        let map = new_map();
+        let mut tracked = TrackedOrdIdMap::new(map, 1);
+        
+        tracked.insert(TestO(10)).unwrap();
+        tracked.insert(TestO(20)).unwrap();
+        assert!(tracked.undo());
+        assert!(!tracked.undo());
+        assert_eq!(tracked.len(), 1);
+    }
+}
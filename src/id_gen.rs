@@ -0,0 +1,108 @@
+//! 与插入解耦的 Id 生成器：多线程预先领取 Id，之后由单线程通过 `insert_with_id` 提交
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use field_collex::{Collexetable, FieldValue};
+use crate::{Id, OrdIdMap};
+
+/// 基于 AtomicU64 的 Id 生成器，克隆后的句柄共享同一个计数器
+///
+/// 生成器与 OrdIdMap 的 max_id 相互独立：使用期间不要再调用 OrdIdMap 的自动生成 Id 的插入，
+/// 提交完成后调用 `OrdIdMap::sync_id_gen` 让后续自动生成的 Id 跳过已领取的部分
+#[derive(Debug)]
+pub struct IdGen<K: Id> {
+    last: Arc<AtomicU64>,
+    _marker: PhantomData<K>,
+}
+
+impl<K: Id> Clone for IdGen<K> {
+    fn clone(&self) -> Self {
+        Self {
+            last: Arc::clone(&self.last),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K: Id> IdGen<K> {
+    /// 创建生成器，生成的第一个 Id 为 `last + 1`
+    pub fn new(last: K) -> Self {
+        Self {
+            last: Arc::new(AtomicU64::new(last.as_u64())),
+            _marker: PhantomData,
+        }
+    }
+    
+    /// 领取下一个 Id
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&self) -> K {
+        K::from_u64(self.last.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+    
+    /// 一次领取 `count` 个连续的 Id
+    pub fn next_n(&self, count: usize) -> impl Iterator<Item = K> {
+        let start = self.last.fetch_add(count as u64, Ordering::Relaxed) + 1;
+        (start..start + count as u64).map(K::from_u64)
+    }
+    
+    /// 目前已领取的最大 Id
+    pub fn last(&self) -> K {
+        K::from_u64(self.last.load(Ordering::Relaxed))
+    }
+}
+
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    /// 从当前 max_id 之后开始生成 Id 的生成器
+    pub fn id_gen(&self) -> IdGen<K> {
+        IdGen::new(self.id_map.max_id())
+    }
+    
+    /// 将 max_id 推进到生成器已领取的最大 Id，之后自动生成的 Id 不会与其重复
+    pub fn sync_id_gen(&mut self, id_gen: &IdGen<K>) {
+        self.id_map.bump_max_id(id_gen.last());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use span_core::Span;
+    use crate::{DefaultId, OrdIdMap};
+    use crate::test_util::TestO;
+    
+    #[test]
+    fn test_id_gen_across_threads() {
+        let mut map: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::new(Span::new_finite(0u32, 1000u32), 10).unwrap();
+        let first = map.insert(TestO(0)).unwrap();
+        let id_gen = map.id_gen();
+        
+        let handles: Vec<_> = (0..4u32)
+            .map(|t| {
+                let id_gen = id_gen.clone();
+                thread::spawn(move || {
+                    (0..10u32)
+                        .map(|i| (id_gen.next(), TestO((t * 10 + i + 1) * 10)))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let mut staged: Vec<_> = handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+        staged.sort_by_key(|(id, _)| id.0);
+        assert_eq!(staged.first().unwrap().0, DefaultId(first.0 + 1));
+        assert_eq!(staged.last().unwrap().0, DefaultId(first.0 + 40));
+        
+        for (id, elem) in staged {
+            map.insert_with_id(id, elem).unwrap();
+        }
+        map.sync_id_gen(&id_gen);
+        
+        assert_eq!(map.len(), 41);
+        assert_eq!(map.insert(TestO(999)).unwrap(), DefaultId(first.0 + 41));
+    }
+}
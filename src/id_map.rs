@@ -2,10 +2,12 @@
 //! 核心特性：插入值自动返回递增 Id、Id 浅包装 u64、无任何条件编译
 
 use core::fmt;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
 use std::marker::PhantomData;
 use std::ops::{Index, IndexMut};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
+use crate::IdError;
 
 // ============================ 核心 Id 定义 ============================
 /// Id 基础 trait，所有自定义 Id 需实现此 trait
@@ -24,22 +26,36 @@ pub trait Id: Copy + Clone + Eq + PartialEq + fmt::Debug + Into<u64> + From<u64>
 
 // ============================ 自定义 Id 生成宏 ============================
 /// 生成自定义 Id 类型的极简宏
+///
+/// 生成的类型带有 `Display`（输出数值）、占位用的 `INVALID`、`as_usize` 与 `is_valid`；
+/// 名称后加 `: Ord` 时额外派生 `PartialOrd` 与 `Ord`
 #[macro_export]
 macro_rules! new_id_type {
     // 递归终止条件：无剩余参数时结束
     () => {};
 
-    // 核心匹配模式：单个 ID 结构体定义（带可选 vis + 属性 + 名称）
-    (
-        $(#[$meta:meta])*
-        $vis:vis struct $name:ident;
-        $($rest:tt)*
-    ) => {
-        // 生成单个 ID 结构体的完整定义
+    // 内部规则：生成单个 ID 结构体的完整定义
+    (@define $(#[$meta:meta])* $vis:vis struct $name:ident) => {
         $(#[$meta])*
         #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
         $vis struct $name(pub u64);
 
+        impl $name {
+            /// 占位用的无效 Id：自动分配的 Id 从 1 开始，不会分配到 0
+            pub const INVALID: Self = Self(0);
+
+            #[inline]
+            pub const fn as_usize(self) -> usize {
+                self.0 as usize
+            }
+
+            /// 是否不是 `INVALID`
+            #[inline]
+            pub const fn is_valid(self) -> bool {
+                self.0 != 0
+            }
+        }
+
         impl From<u64> for $name {
             #[inline]
             fn from(val: u64) -> Self {
@@ -54,29 +70,53 @@ macro_rules! new_id_type {
             }
         }
 
+        impl ::core::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                ::core::fmt::Display::fmt(&self.0, f)
+            }
+        }
+
         impl $crate::Id for $name {}
 
-        impl serde::Serialize for $name {
+        impl $crate::serde::Serialize for $name {
             #[inline]
-            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            fn serialize<S>(&self, serializer: S) -> ::core::result::Result<S::Ok, S::Error>
             where
-                S: serde::Serializer,
+                S: $crate::serde::Serializer,
             {
-                self.0.serialize(serializer)
+                $crate::serde::Serialize::serialize(&self.0, serializer)
             }
         }
 
-        impl<'de> serde::Deserialize<'de> for $name {
+        impl<'de> $crate::serde::Deserialize<'de> for $name {
             #[inline]
-            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            fn deserialize<D>(deserializer: D) -> ::core::result::Result<Self, D::Error>
             where
-                D: serde::Deserializer<'de>,
+                D: $crate::serde::Deserializer<'de>,
             {
-                let val = u64::deserialize(deserializer)?;
-                Ok(Self(val))
+                let val = <u64 as $crate::serde::Deserialize>::deserialize(deserializer)?;
+                ::core::result::Result::Ok(Self(val))
             }
         }
+    };
 
+    // 带 `: Ord` 的 ID 结构体：额外派生全序
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident : Ord;
+        $($rest:tt)*
+    ) => {
+        $crate::new_id_type!(@define $(#[$meta])* #[derive(PartialOrd, Ord)] $vis struct $name);
+        $crate::new_id_type!($($rest)*);
+    };
+
+    // 核心匹配模式：单个 ID 结构体定义（带可选 vis + 属性 + 名称）
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident;
+        $($rest:tt)*
+    ) => {
+        $crate::new_id_type!(@define $(#[$meta])* $vis struct $name);
         $crate::new_id_type!($($rest)*);
     };
 }
@@ -88,9 +128,12 @@ new_id_type!{
 
 // ============================ IdMap 核心实现（自动生成递增 Id） ============================
 /// 极简版 IdMap：自动生成递增 Id + HashMap 存储 + 无条件编译
+///
+/// 序列化时按 Id 升序输出，相同内容总是得到相同的字节
 #[derive(Debug, Clone)]
 #[derive(Serialize, Deserialize)]
 pub struct IdMap<K: Id, V> {
+    #[serde(serialize_with = "serialize_sorted", bound(serialize = "V: Serialize"))]
     pub(crate) inner: HashMap<u64, V>, // 底层存储：u64 -> V
     #[serde(skip)]
     max_id: u64,            // 记录最大 Id，用于生成递增 Id
@@ -98,6 +141,12 @@ pub struct IdMap<K: Id, V> {
     _marker: PhantomData<K>,
 }
 
+impl<K: Id, V> Default for IdMap<K, V> {
+    fn default() -> Self {
+        Self::with_id()
+    }
+}
+
 impl<V> IdMap<DefaultId, V> {
     /// 创建空的 IdMap（初始 max_id = 0）
     pub fn new() -> Self { Self::with_id_capacity(0) }
@@ -194,6 +243,18 @@ impl<K: Id, V> IdMap<K, V> {
         self.inner.remove(&id.as_u64())
     }
     
+    /// 批量删除；数量超过现有条目一半时整表过滤一次，否则逐个删除
+    pub fn remove_all(&mut self, ids: &[K]) {
+        if ids.len() * 2 >= self.inner.len() {
+            let gone: HashSet<u64> = ids.iter().map(|id| id.as_u64()).collect();
+            self.inner.retain(|id, _| !gone.contains(id));
+        } else {
+            for id in ids {
+                self.inner.remove(&id.as_u64());
+            }
+        }
+    }
+    
     /// 判断是否包含指定 Id
     pub fn contains_id(&self, id: K) -> bool {
         self.inner.contains_key(&id.as_u64())
@@ -204,6 +265,37 @@ impl<K: Id, V> IdMap<K, V> {
         K::from_u64(self.max_id)
     }
     
+    /// 下一次自动生成的 Id（不插入）；max_id 已达 `u64::MAX`、再无可分配的 Id 时返回 None
+    pub fn peek_next_id(&self) -> Option<K> {
+        self.max_id.checked_add(1).map(K::from_u64)
+    }
+    
+    /// 遍历所有 `(K, &V)`（顺序不保证）
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)> {
+        self.inner.iter().map(|(id, v)| (K::from_u64(*id), v))
+    }
+    
+    /// 生成下一个递增 Id 但不插入值，供先校验、成功后再用 `insert_with_id` 登记的插入路径使用
+    pub(crate) fn next_id(&mut self) -> K {
+        self.max_id += 1;
+        K::from_u64(self.max_id)
+    }
+    
+    /// 底层 HashMap 的 entry，查询与更新只需一次哈希；不会更新 max_id
+    pub(crate) fn entry(&mut self, id: K) -> Entry<'_, u64, V> {
+        self.inner.entry(id.as_u64())
+    }
+    
+    /// 确保 max_id 不小于给定 Id，之后自动生成的 Id 都会大于它
+    pub(crate) fn bump_max_id(&mut self, id: K) {
+        self.max_id = self.max_id.max(id.as_u64());
+    }
+    
+    /// 直接设置 max_id，仅供回滚等需要恢复历史状态的场景使用
+    pub(crate) fn set_max_id(&mut self, id: K) {
+        self.max_id = id.as_u64();
+    }
+    
     /// 获取元素数量
     pub fn len(&self) -> usize {
         self.inner.len()
@@ -214,12 +306,160 @@ impl<K: Id, V> IdMap<K, V> {
         self.inner.is_empty()
     }
     
+    /// 获取当前容量
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+    
+    /// 预留至少可再容纳 additional 个元素的空间
+    pub fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional);
+    }
+    
+    /// 尽可能释放多余的容量
+    pub fn shrink_to_fit(&mut self) {
+        self.inner.shrink_to_fit();
+    }
+    
     /// 清空所有元素（保留 max_id 不变，避免 Id 重复）
     pub fn clear(&mut self) {
         self.inner.clear();
     }
+    
+    /// 根据 Id 查询值，区分「已删除」与「从未分配」
+    ///
+    /// Id 递增且不复用，因此不大于 max_id 却查不到的 Id 必然已被删除
+    pub fn try_get(&self, id: K) -> Result<&V, IdError<K>> {
+        self.get(id).ok_or(
+            if id.as_u64() <= self.max_id { IdError::Stale(id) } else { IdError::NeverExisted(id) }
+        )
+    }
+}
+
+// ============================ GenIdMap 实现（代际 Id，可复用槽位） ============================
+/// 代际 Id 的 IdMap：Id = 代数（高 32 位）+ 槽位下标（低 32 位）
+///
+/// 删除后槽位会被复用并递增代数，旧 Id 不会误指向新值，可通过 `try_get` 识别为过期句柄
+#[derive(Debug, Clone)]
+pub struct GenIdMap<K: Id, V> {
+    slots: Vec<(u32, Option<V>)>, // 底层存储：(代数, 值)
+    free: Vec<u32>,               // 空闲槽位下标
+    len: usize,
+    _marker: PhantomData<K>,
+}
+
+impl<K: Id, V> Default for GenIdMap<K, V> {
+    fn default() -> Self {
+        Self::with_id()
+    }
+}
+
+impl<K: Id, V> GenIdMap<K, V> {
+    /// 创建空的 GenIdMap
+    pub fn with_id() -> Self {
+        Self::with_id_capacity(0)
+    }
+    
+    /// 创建指定初始容量的 GenIdMap
+    pub fn with_id_capacity(capacity: usize) -> Self {
+        Self {
+            slots: Vec::with_capacity(capacity),
+            free: Vec::new(),
+            len: 0,
+            _marker: PhantomData,
+        }
+    }
+    
+    fn encode(index: u32, generation: u32) -> K {
+        K::from_u64(((generation as u64) << 32) | index as u64)
+    }
+    
+    fn decode(id: K) -> (usize, u32) {
+        let raw = id.as_u64();
+        ((raw & u32::MAX as u64) as usize, (raw >> 32) as u32)
+    }
+    
+    /// 插入值，优先复用空闲槽位，返回代际 Id
+    pub fn insert(&mut self, value: V) -> K {
+        self.len += 1;
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.1 = Some(value);
+                Self::encode(index, slot.0)
+            }
+            None => {
+                let index = self.slots.len() as u32;
+                self.slots.push((0, Some(value)));
+                Self::encode(index, 0)
+            }
+        }
+    }
+    
+    /// 根据 Id 查询值，区分「过期句柄」与「从未分配」
+    pub fn try_get(&self, id: K) -> Result<&V, IdError<K>> {
+        let (index, generation) = Self::decode(id);
+        match self.slots.get(index) {
+            Some((current, Some(value))) if *current == generation => Ok(value),
+            Some((current, _)) if generation <= *current => Err(IdError::Stale(id)),
+            _ => Err(IdError::NeverExisted(id)),
+        }
+    }
+    
+    /// 根据 Id 查询值
+    pub fn get(&self, id: K) -> Option<&V> {
+        self.try_get(id).ok()
+    }
+    
+    /// 根据 Id 查询可变值
+    pub fn get_mut(&mut self, id: K) -> Option<&mut V> {
+        let (index, generation) = Self::decode(id);
+        match self.slots.get_mut(index) {
+            Some((current, value)) if *current == generation => value.as_mut(),
+            _ => None,
+        }
+    }
+    
+    /// 根据 Id 删除值，槽位代数递增后进入空闲列表
+    pub fn remove(&mut self, id: K) -> Option<V> {
+        let (index, generation) = Self::decode(id);
+        let (current, slot) = self.slots.get_mut(index)?;
+        if *current != generation {
+            return None;
+        }
+        let value = slot.take()?;
+        *current = current.wrapping_add(1);
+        self.free.push(index as u32);
+        self.len -= 1;
+        Some(value)
+    }
+    
+    /// 判断是否包含指定 Id
+    pub fn contains_id(&self, id: K) -> bool {
+        self.get(id).is_some()
+    }
+    
+    /// 获取元素数量
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    
+    /// 判断是否为空
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+// ============================ PartialEq/Eq 实现 ============================
+/// 仅比较存储的 Id → 值，不比较 max_id（max_id 不参与序列化）
+impl<K: Id, V: PartialEq> PartialEq for IdMap<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
 }
 
+impl<K: Id, V: Eq> Eq for IdMap<K, V> {}
+
 // ============================ Index/IndexMut 实现 ============================
 impl<K: Id, V> Index<K> for IdMap<K, V> {
     type Output = V;
@@ -235,6 +475,17 @@ impl<K: Id, V> IndexMut<K> for IdMap<K, V> {
     }
 }
 
+/// 按 Id 升序序列化 HashMap，使输出与哈希顺序无关
+fn serialize_sorted<V, S>(inner: &HashMap<u64, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    V: Serialize,
+    S: Serializer,
+{
+    let mut entries: Vec<_> = inner.iter().collect();
+    entries.sort_unstable_by_key(|(id, _)| **id);
+    serializer.collect_map(entries)
+}
+
 // ============================ 测试用例 ============================
 #[cfg(test)]
 mod tests {
@@ -294,6 +545,61 @@ mod tests {
         assert!(!map.contains_id(id1));
     }
     
+    new_id_type! {
+        struct OrdId: Ord;
+    }
+    
+    // 测试宏生成的辅助项
+    #[test]
+    fn test_id_helpers() {
+        let id = MyId(7);
+        assert_eq!(id.to_string(), "7");
+        assert_eq!(id.as_usize(), 7);
+        assert!(id.is_valid());
+        assert!(!MyId::INVALID.is_valid());
+        
+        let mut ids = vec![OrdId(3), OrdId(1), OrdId(2)];
+        ids.sort();
+        assert_eq!(ids, vec![OrdId(1), OrdId(2), OrdId(3)]);
+        assert!(OrdId::INVALID < OrdId(1));
+    }
+    
+    // 测试相等性：只比较内容，不比较 max_id
+    #[test]
+    fn test_id_map_eq() {
+        let mut a = IdMap::new();
+        let id = a.insert(1);
+        let mut b = IdMap::new();
+        b.insert_with_id(id, 1);
+        assert_eq!(a, b);
+        
+        a.insert(2);
+        assert_ne!(a, b);
+        
+        let removed = a.max_id();
+        a.remove(removed);
+        assert_eq!(a, b);
+    }
+    
+    // 测试过期句柄识别
+    #[test]
+    fn test_stale_id() {
+        let mut map = IdMap::new();
+        let id = map.insert(1);
+        map.remove(id);
+        assert_eq!(map.try_get(id), Err(IdError::Stale(id)));
+        assert_eq!(map.try_get(DefaultId(9)), Err(IdError::NeverExisted(DefaultId(9))));
+        
+        let mut gen_map = GenIdMap::<DefaultId, u32>::with_id();
+        let old = gen_map.insert(1);
+        gen_map.remove(old);
+        let new = gen_map.insert(2);
+        assert_ne!(old, new); // 槽位复用，代数不同
+        assert_eq!(gen_map.try_get(old), Err(IdError::Stale(old)));
+        assert_eq!(gen_map.try_get(new), Ok(&2));
+        assert_eq!(gen_map.try_get(DefaultId(5)), Err(IdError::NeverExisted(DefaultId(5))));
+    }
+    
     // 测试 Id 透明序列化
     #[test]
     fn test_id_serde() {
@@ -310,4 +616,18 @@ mod tests {
         let my_id2: MyId = serde_json::from_str(&json).unwrap();
         assert_eq!(my_id2, my_id);
     }
-}
\ No newline at end of file
+    
+    // 测试序列化输出与插入顺序无关
+    #[test]
+    fn test_id_map_serde_sorted() {
+        let mut a = IdMap::new();
+        let mut b = IdMap::new();
+        for id in 1..=50u64 {
+            a.insert_with_id(DefaultId(id), id * 10);
+            b.insert_with_id(DefaultId(51 - id), (51 - id) * 10);
+        }
+        let json = serde_json::to_string(&a).unwrap();
+        assert_eq!(json, serde_json::to_string(&b).unwrap());
+        assert!(json.starts_with(r#"{"inner":{"1":10,"2":20,"#));
+    }
+}
@@ -0,0 +1,195 @@
+//! 不维护 id_map 的模式：一次写入、多次遍历时省去 HashMap 的内存与插入开销
+//!
+//! 延迟索引：批量载入时先不建 id_map，到首次按 Id 的可变操作时一次性建立
+
+use field_collex::{Collexetable, FieldCollex, FieldValue};
+use field_collex::collex::{NewFieldCollexError, WithElementsFieldCollexError};
+use span_core::Span;
+use crate::{extend_from_iter, scan_id, ChangeLog, Id, IdMap, InconsistentState, OrdIdMap, ShrinkPolicy};
+
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    /// 不维护 id_map 的空 OrdIdMap，适合一次写入、多次遍历的场景
+    ///
+    /// 插入与批量插入不再写 HashMap；按 Id 的查询与修改退化为线性扫描 collex。
+    /// 之后可用 `build_index` 一次性建立索引，恢复常数时间的按 Id 访问
+    pub fn without_index(span: Span<V>, unit: V) -> Result<Self, NewFieldCollexError<V>> {
+        let mut map = Self::new(span, unit)?;
+        map.indexed = false;
+        Ok(map)
+    }
+    
+    /// 是否维护 id_map
+    pub fn is_indexed(&self) -> bool {
+        self.indexed
+    }
+    
+    /// 以给定元素构造，但延迟建立 id_map
+    ///
+    /// 只做遍历时完全不产生 HashMap 开销；首次按 Id 的可变操作（remove、modify 等）时一次性建立索引。
+    /// 在此之前按 Id 的只读查询退化为扫描，也可随时调用 `build_index`
+    pub fn with_elements_lazy(
+        span: Span<V>,
+        unit: V,
+        vec: Vec<E>,
+    ) -> Result<Self, WithElementsFieldCollexError<V>>
+    {
+        let mut id_map = IdMap::with_id();
+        let pairs = extend_from_iter(&mut id_map, vec, false);
+        
+        Ok(Self{
+            id_map,
+            collex: FieldCollex::with_elements(span, unit, pairs)?,
+            changes: ChangeLog::default(),
+            next_fit: None,
+            indexed: false,
+            lazy_index: true,
+            shrink_policy: ShrinkPolicy::default(),
+        })
+    }
+    
+    /// 批量插入并延迟建立索引：已有的 id_map 被丢弃，到首次按 Id 的可变操作时连同新元素一次性重建
+    pub fn extend_lazy<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = E>,
+    {
+        if self.indexed {
+            self.drop_index();
+        }
+        self.lazy_index = true;
+        self.extend(iter);
+    }
+    
+    /// 若处于延迟索引状态，立即建立索引
+    pub(crate) fn ensure_index(&mut self) {
+        if self.lazy_index {
+            self.build_index();
+        }
+    }
+    
+    /// 按 collex 一次性建立 id_map，之后按 Id 的访问恢复为常数时间；已建索引时什么也不做
+    pub fn build_index(&mut self) {
+        self.lazy_index = false;
+        if !self.indexed {
+            self.indexed = true;
+            self.rebuild_index();
+        }
+    }
+    
+    /// 丢弃 id_map 中的条目（保留 max_id），转为不维护索引的模式
+    pub fn drop_index(&mut self) {
+        self.id_map.clear();
+        self.id_map.shrink_to_fit();
+        self.indexed = false;
+        self.lazy_index = false;
+    }
+    
+    /// Id 对应元素的 field 值：有索引时查 id_map，否则扫描 collex
+    pub(crate) fn locate(&self, id: K) -> Option<V> {
+        if self.indexed {
+            self.id_map.get(id).copied()
+        } else {
+            scan_id(&self.collex, id)
+        }
+    }
+    
+    /// 同 `locate`，但确认 collex 在该位置上确实是这个 Id 的元素
+    pub(crate) fn locate_checked(&self, id: K) -> Result<Option<V>, InconsistentState<K, V>> {
+        match self.locate(id) {
+            Some(value) if !self.collex.get(value).is_some_and(|p| p.0 == id) => {
+                Err(InconsistentState { id, value })
+            }
+            located => Ok(located),
+        }
+    }
+    
+    /// 登记 Id 的新 field 值；未建索引时只推进 max_id
+    pub(crate) fn set_index(&mut self, id: K, v: V) {
+        if self.indexed {
+            self.id_map.insert_with_id(id, v);
+        } else {
+            self.id_map.bump_max_id(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use span_core::Span;
+    use crate::{DefaultId, InconsistentState, OrdIdMap};
+    use crate::test_util::{TestO, new_map};
+    
+    #[test]
+    fn test_without_index() {
+        let mut map: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::without_index(Span::new_finite(0u32, 100u32), 10).unwrap();
+        map.extend([TestO(10), TestO(20)]);
+        let c = map.insert(TestO(30)).unwrap();
+        assert!(!map.is_indexed());
+        assert_eq!(map.id_map.len(), 0);
+        assert_eq!(c, DefaultId(3));
+        
+        // 按 Id 的访问退化为扫描
+        assert_eq!(map.get_with_id(DefaultId(2)), Some(&TestO(20)));
+        map.modify(DefaultId(2), |e| e.0 = 50).unwrap();
+        assert_eq!(map.remove(DefaultId(1)), Some(TestO(10)));
+        assert!(!map.contains_id(DefaultId(1)));
+        assert!(map.validate().is_ok());
+        
+        map.build_index();
+        assert!(map.is_indexed());
+        assert_eq!(map.id_map.len(), 2);
+        assert_eq!(map.id_map.max_id(), c);
+        assert_eq!(map.get_with_id(DefaultId(2)), Some(&TestO(50)));
+        assert!(map.validate().is_ok());
+        assert_eq!(map.insert(TestO(60)).unwrap(), DefaultId(4));
+    }
+    
+    #[test]
+    fn test_lazy_index() {
+        let mut map: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::with_elements_lazy(
+            Span::new_finite(0u32, 100u32),
+            10,
+            vec![TestO(10), TestO(20), TestO(30)],
+        ).unwrap();
+        assert!(!map.is_indexed());
+        assert_eq!(map.iter().count(), 3);
+        assert_eq!(map.get_with_id(DefaultId(2)), Some(&TestO(20)));
+        assert!(!map.is_indexed());
+        
+        // 首次按 Id 的可变操作建立索引
+        map.remove(DefaultId(1));
+        assert!(map.is_indexed());
+        assert_eq!(map.id_map.len(), 2);
+        
+        map.extend_lazy([TestO(40), TestO(50)]);
+        assert!(!map.is_indexed());
+        map.modify(DefaultId(5), |e| e.0 = 60).unwrap();
+        assert!(map.is_indexed());
+        assert_eq!(map.id_map.len(), 4);
+        assert!(map.validate().is_ok());
+    }
+    
+    #[test]
+    fn test_inconsistent_state() {
+        let mut map = new_map();
+        let a = map.insert(TestO(10)).unwrap();
+        let b = map.insert(TestO(20)).unwrap();
+        // 人为让 a 的索引指向 b 的位置
+        map.id_map.insert_with_id(a, 20);
+        
+        assert_eq!(map.try_get_with_id(a), Err(InconsistentState { id: a, value: 20 }));
+        assert_eq!(map.get_with_id(a), None);
+        assert_eq!(map.try_remove(a), Err(InconsistentState { id: a, value: 20 }));
+        assert_eq!(map.remove(a), None);
+        assert!(map.modify(a, |e| e.0 = 30).is_err());
+        assert_eq!(map.get_with_id(b), Some(&TestO(20)));
+        
+        map.rebuild_index();
+        assert_eq!(map.remove(a), Some(TestO(10)));
+        assert!(map.validate().is_ok());
+    }
+}
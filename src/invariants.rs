@@ -0,0 +1,56 @@
+//! 开启 `debug-invariants` feature 后，每个修改操作结束时在 debug 构建中校验 id_map 与 collex 是否一致
+
+use field_collex::{Collexetable, FieldValue};
+use crate::{Id, OrdIdMap};
+
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    /// 校验元素数量，并抽查本次操作涉及的 Id 与 field 值最小的元素；未开启 feature 时为空操作
+    #[inline]
+    pub(crate) fn debug_check_invariants(&self, touched: Option<K>) {
+        #[cfg(feature = "debug-invariants")]
+        {
+            if self.indexed {
+                debug_assert_eq!(
+                    self.id_map.len(), self.collex.iter().count(),
+                    "id_map and collex hold different numbers of elements"
+                );
+            }
+            let first = self.collex.iter().next().map(|p| p.0);
+            for id in touched.into_iter().chain(first) {
+                debug_assert!(self.locate_checked(id).is_ok(), "id_map and collex disagree on {:?}", id);
+            }
+        }
+        #[cfg(not(feature = "debug-invariants"))]
+        let _ = touched;
+    }
+}
+
+#[cfg(all(test, feature = "debug-invariants"))]
+mod tests {
+    use crate::test_util::{TestO, new_map};
+    
+    #[test]
+    #[should_panic(expected = "id_map and collex disagree")]
+    fn test_desync_caught_on_next_mutation() {
+        let mut map = new_map();
+        let a = map.insert(TestO(10)).unwrap();
+        map.insert(TestO(20)).unwrap();
+        map.id_map.insert_with_id(a, 20);
+        let _ = map.insert(TestO(30));
+    }
+    
+    #[test]
+    fn test_extend_with_rejected_elements() {
+        let mut map = new_map();
+        map.insert(TestO(10)).unwrap();
+        map.extend([TestO(20), TestO(200), TestO(10)]);
+        let _ = map.try_extend([TestO(30), TestO(300), TestO(20)]);
+        assert_eq!(map.id_map.len(), 3);
+        assert!(map.validate().is_ok());
+    }
+}
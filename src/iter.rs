@@ -0,0 +1,140 @@
+use field_collex::{Collexetable, FieldCollex, FieldValue};
+use field_collex::collex::serialize::{default_span, default_unit};
+use crate::{Id, IdMap, OrdIdMap, Pair};
+
+/// 消耗 OrdIdMap，按 field 顺序产出 `Pair<K, E>`，同时逐个释放 id_map 中的条目
+pub struct IntoIter<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    id_map: IdMap<K, V>,
+    inner: <FieldCollex<Pair<K, E>, V> as IntoIterator>::IntoIter,
+}
+
+impl<K, E, V> Iterator for IntoIter<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    type Item = Pair<K, E>;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        let pair = self.inner.next()?;
+        self.id_map.remove(pair.0);
+        Some(pair)
+    }
+    
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, E, V> IntoIterator for OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    type Item = Pair<K, E>;
+    type IntoIter = IntoIter<K, E, V>;
+    
+    fn into_iter(self) -> Self::IntoIter {
+        let (id_map, collex) = self.into_raw_parts();
+        IntoIter {
+            id_map,
+            inner: collex.into_iter(),
+        }
+    }
+}
+
+/// 以默认 span（从 0 开始的无限区间）与默认 unit 收集元素
+///
+/// # Panics
+///
+/// 任一元素的 field 值不在默认 span 内（如负数）时 panic；需要处理越界时用 `from_iter_with`
+impl<K, E, V> FromIterator<E> for OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    fn from_iter<I: IntoIterator<Item = E>>(iter: I) -> Self {
+        let span = default_span();
+        let elements: Vec<E> = iter.into_iter().collect();
+        assert!(
+            elements.iter().all(|e| span.contains(e.collexate_ref())),
+            "elements do not fit the default span"
+        );
+        let Ok(map) = Self::with_elements(span, default_unit(), elements) else {
+            unreachable!("the default span and unit are always valid")
+        };
+        map
+    }
+}
+
+impl<K, E, V> Extend<E> for OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    fn extend<I: IntoIterator<Item = E>>(&mut self, iter: I) {
+        OrdIdMap::extend(self, iter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use field_collex::Collexetable;
+    use span_core::Span;
+    use crate::{DefaultId, OrdIdMap, Pair};
+    use crate::test_util::TestO;
+    
+    struct Signed(i32);
+    impl Collexetable<i32> for Signed {
+        fn collexate(&self) -> i32 { self.0 }
+        
+        fn collexate_ref(&self) -> &i32 {
+            &self.0
+        }
+        
+        fn collexate_mut(&mut self) -> &mut i32 {
+            &mut self.0
+        }
+    }
+    
+    #[test]
+    fn test_iter_ordered_and_into_iter() {
+        let map: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::from_iter_with(
+            Span::new_finite(0u32, 100u32),
+            10,
+            [TestO(30), TestO(10), TestO(20)],
+        ).unwrap();
+        
+        let values: Vec<u32> = map.iter_ordered().map(|(_, e)| e.0).collect();
+        assert_eq!(values, vec![10, 20, 30]);
+        
+        let pairs: Vec<Pair<DefaultId, TestO>> = map.into_iter().collect();
+        assert_eq!(pairs, vec![
+            Pair(DefaultId(2), TestO(10)),
+            Pair(DefaultId(3), TestO(20)),
+            Pair(DefaultId(1), TestO(30)),
+        ]);
+    }
+    
+    #[test]
+    fn test_collect() {
+        let map: OrdIdMap<DefaultId, TestO, u32> = [TestO(500), TestO(3)].into_iter().collect();
+        assert_eq!(map.ids().collect::<Vec<_>>(), vec![DefaultId(2), DefaultId(1)]);
+        assert!(map.validate().is_ok());
+    }
+    
+    #[test]
+    #[should_panic(expected = "elements do not fit the default span")]
+    fn test_collect_out_of_default_span() {
+        let _: OrdIdMap<DefaultId, Signed, i32> = [Signed(1), Signed(-1)].into_iter().collect();
+    }
+}
@@ -2,11 +2,99 @@
 pub mod pair;
 pub mod id_map;
 pub mod deser;
+pub mod guard;
+pub mod iter;
+pub mod error;
+pub mod secondary;
+pub mod report;
+pub mod view;
+pub mod snapshot;
+pub mod history;
+pub mod txn;
+pub mod changes;
+pub mod entry;
+pub mod cursor;
+pub mod alloc;
+pub mod stats;
+pub mod bitmap;
+pub mod meta;
+pub mod full;
+pub mod delta;
+pub mod persist;
+pub mod wal;
+pub mod shared;
+pub mod id_gen;
+pub mod sharded;
+pub mod actor;
+pub mod cow;
+pub mod index;
+pub mod arena;
+pub mod small;
+pub mod shrink;
+pub mod builder;
+mod macros;
+pub mod detail;
+mod invariants;
+pub mod prelude;
+#[cfg(feature = "export")]
+pub mod export;
+#[cfg(feature = "rayon")]
+pub mod par;
+#[cfg(feature = "tokio")]
+pub mod async_shared;
+#[cfg(test)]
+pub(crate) mod test_util;
 
 pub use id_map::*;
+pub use deser::*;
 pub use pair::*;
+pub use guard::*;
+pub use iter::*;
+pub use error::*;
+pub use secondary::*;
+pub use report::*;
+pub use view::*;
+pub use snapshot::*;
+pub use history::*;
+pub use txn::*;
+pub use changes::*;
+pub use entry::*;
+pub use cursor::*;
+pub use alloc::*;
+pub use stats::*;
+pub use bitmap::*;
+pub use meta::*;
+pub use full::*;
+pub use delta::*;
+pub use wal::*;
+pub use shared::*;
+pub use id_gen::*;
+pub use sharded::*;
+pub use actor::*;
+pub use cow::*;
+pub use arena::*;
+pub use small::*;
+pub use shrink::*;
+pub use builder::*;
+#[cfg(feature = "rayon")]
+pub use par::*;
+#[cfg(feature = "tokio")]
+pub use async_shared::*;
 
-use std::ops::{Deref, DerefMut};
+// 供宏展开使用，使调用方不必直接依赖这些 crate
+#[doc(hidden)]
+pub use span_core;
+#[doc(hidden)]
+pub use field_collex;
+#[doc(hidden)]
+pub use serde;
+
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::collections::hash_map::Entry as MapEntry;
+use std::ops::Deref;
+use std::rc::Rc;
 use field_collex::{Collexetable, FieldCollex, FieldValue};
 use field_collex::collex::*;
 use span_core::Span;
@@ -23,22 +111,108 @@ where
     )
 }
 
-pub(crate) fn extend_from_vec<K,E,V>(id_map: &mut IdMap<K,V>, vec: Vec<E>) -> Vec<Pair<K,E>>
+/// 逐个为元素分配 Id 并登记到 id_map，按迭代器的下界预先为 id_map 预留容量
+///
+/// `indexed` 为 false 时只分配 Id，不登记
+pub(crate) fn pair_up<'a,K,E,V,I>(id_map: &'a mut IdMap<K,V>, iter: I, indexed: bool) -> impl Iterator<Item = Pair<K,E>> + 'a
+where
+    K: Id + 'a,
+    E: Collexetable<V>,
+    V: FieldValue + 'a,
+    I: IntoIterator<Item = E>,
+    I::IntoIter: 'a,
+{
+    let iter = iter.into_iter();
+    if indexed {
+        id_map.reserve(iter.size_hint().0);
+    }
+    iter.map(move |e| if indexed { insert(id_map, e) } else { Pair(id_map.next_id(), e) })
+}
+
+pub(crate) fn extend_from_iter<K,E,V,I>(id_map: &mut IdMap<K,V>, iter: I, indexed: bool) -> Vec<Pair<K,E>>
 where
     K: Id,
     E: Collexetable<V>,
     V: FieldValue,
+    I: IntoIterator<Item = E>,
 {
-    let mut other: Vec<Pair<K,E>> = Vec::new();
-    vec.into_iter().for_each(|e|
-        {
-            other.push(insert(id_map, e))
+    pair_up(id_map, iter, indexed).collect()
+}
+
+/// 由元素自带的 Id 构建 id_map，max_id 为其中最大的 Id
+///
+/// field 值经 `collexate_ref` 直接读取，不为每个元素重新计算 `collexate`
+pub(crate) fn build_index<'a,K,E,V,I>(elements: I, capacity: usize) -> IdMap<K,V>
+where
+    K: Id + 'a,
+    E: Collexetable<V> + 'a,
+    V: FieldValue,
+    I: Iterator<Item = &'a Pair<K,E>>,
+{
+    let mut id_map = IdMap::with_id_capacity(capacity);
+    for obj in elements {
+        id_map.insert_with_id(obj.0, *obj.1.collexate_ref());
+    }
+    id_map
+}
+
+/// 不经 id_map，线性扫描 collex 找出 Id 对应元素的 field 值
+pub(crate) fn scan_id<K,E,V>(collex: &FieldCollex<Pair<K,E>,V>, id: K) -> Option<V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    collex.iter().find(|p| p.0 == id).map(|p| *p.collexate_ref())
+}
+
+/// 把 `from` 中的元素逐个移入 `to`，放不进 `to` 的元素交给 `rejected` 后放回 `from`
+pub(crate) fn move_all<P,V,F>(from: &mut FieldCollex<P,V>, to: &mut FieldCollex<P,V>, mut rejected: F)
+where
+    P: Collexetable<V>,
+    V: FieldValue,
+    F: FnMut(&P),
+{
+    let values: Vec<V> = from.iter().map(|p| p.collexate()).collect();
+    for v in values {
+        let Ok(p) = from.remove(v) else { continue };
+        if let Err(InsertFieldCollexError::OutOfSpan(p) | InsertFieldCollexError::AlreadyExist(p)) = to.insert(p) {
+            rejected(&p);
+            let _ = from.insert(p);
         }
-    );
-    
-    other
+    }
+}
+
+/// 单趟重建 collex：按 field 顺序取出所有元素，`keep` 为真的放回，其余按序返回
+///
+/// 重建后的 collex 沿用原有容量（不超过最大块数）
+pub(crate) fn rebuild_collex<P,V,F>(collex: &mut FieldCollex<P,V>, mut keep: F) -> Vec<P>
+where
+    P: Collexetable<V>,
+    V: FieldValue,
+    F: FnMut(&P) -> bool,
+{
+    let capacity = collex.size().map_or(collex.capacity(), |size| size.min(collex.capacity()));
+    let Ok(empty) = FieldCollex::with_capacity(collex.span().clone(), *collex.unit(), capacity) else {
+        unreachable!("span and unit come from an existing collex")
+    };
+    let old = std::mem::replace(collex, empty);
+    let mut dropped = Vec::new();
+    for p in old {
+        if keep(&p) {
+            // 元素来自 span、unit 相同的 collex，且按 field 顺序放回，不会失败
+            let _ = collex.insert(p);
+        } else {
+            dropped.push(p);
+        }
+    }
+    dropped
 }
 
+/// 序列化只输出 collex，元素按 field 顺序排列，与哈希顺序无关：相同内容总是得到相同的字节
+///
+/// K、E、V 均为 Send / Sync 时，OrdIdMap 也是 Send / Sync（见下方的静态断言），可在线程间移动与共享。
+/// 各种可变访问守卫借助 Rc 统一写回，不可跨线程
 #[derive(Debug)]
 #[derive(serde::Serialize)]
 #[serde(transparent)]
@@ -48,11 +222,50 @@ where
     O: Collexetable<T>,
     T: FieldValue,
 {
+    /// 只读访问见 `id_map()`；不对外可写，避免与 collex 失去同步
+    #[serde(skip)]
+    pub(crate) id_map: IdMap<K,T>,
+    /// 只读访问见 `collex()`，可变访问只能经 `collex_mut_guard` 或 `collex_mut_unchecked`
+    pub(crate) collex: FieldCollex<Pair<K,O>,T>,
+    #[serde(skip)]
+    pub(crate) changes: ChangeLog<K>,
+    /// `PlacementStrategy::NextFit` 下次开始查找的位置
     #[serde(skip)]
-    pub id_map: IdMap<K,T>,
-    pub collex: FieldCollex<Pair<K,O>,T>
+    pub(crate) next_fit: Option<T>,
+    /// 是否维护 id_map；为 false 时 id_map 只用于分配 Id，不含任何条目
+    #[serde(skip)]
+    pub(crate) indexed: bool,
+    /// 未建索引时，是否在首次按 Id 的可变操作时自动建立
+    #[serde(skip)]
+    pub(crate) lazy_index: bool,
+    /// 删除元素后是否释放多余容量
+    #[serde(skip)]
+    pub(crate) shrink_policy: ShrinkPolicy,
 }
 
+// 静态断言：参数满足 Send / Sync 时，对外的主要类型也满足，任何内部改动破坏这一点都会编译失败
+const _: () = {
+    fn assert_send_sync<T: Send + Sync>() {}
+    
+    fn assert_all<'a, K, E, V>()
+    where
+        K: Id + Send + Sync,
+        E: Collexetable<V> + Send + Sync + 'a,
+        V: FieldValue + Send + Sync,
+    {
+        assert_send_sync::<OrdIdMap<K, E, V>>();
+        assert_send_sync::<IdMap<K, V>>();
+        assert_send_sync::<GenIdMap<K, V>>();
+        assert_send_sync::<Pair<K, E>>();
+        assert_send_sync::<PairRef<'a, K, E>>();
+        assert_send_sync::<ChangeLog<K>>();
+        assert_send_sync::<SecondaryMap<K, E>>();
+        assert_send_sync::<SparseSecondaryMap<K, E>>();
+        assert_send_sync::<Snapshot<K, E, V>>();
+        assert_send_sync::<IntoIter<K, E, V>>();
+    }
+};
+
 impl<K,E,V> Deref for OrdIdMap<K,E,V>
 where
     K: Id,
@@ -65,17 +278,52 @@ where
     }
 }
 
-impl<K,E,V> DerefMut for OrdIdMap<K,E,V>
+/// FieldCollex 未实现 Clone，按 field 顺序复制元素后重建
+impl<K,E,V> Clone for OrdIdMap<K,E,V>
 where
     K: Id,
-    E: Collexetable<V>,
+    E: Collexetable<V> + Clone,
     V: FieldValue,
 {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.collex
+    fn clone(&self) -> Self {
+        let pairs: Vec<Pair<K,E>> = self.collex.iter().cloned().collect();
+        let Ok(collex) = FieldCollex::with_elements(self.collex.span().clone(), *self.collex.unit(), pairs) else {
+            unreachable!("span and unit come from an existing collex")
+        };
+        Self {
+            id_map: self.id_map.clone(),
+            collex,
+            changes: self.changes.clone(),
+            next_fit: self.next_fit,
+            indexed: self.indexed,
+            lazy_index: self.lazy_index,
+            shrink_policy: self.shrink_policy,
+        }
+    }
+}
+
+/// 结构相等：span、unit 相同，且相同的 Id 指向相等的元素
+impl<K,E,V> PartialEq for OrdIdMap<K,E,V>
+where
+    K: Id,
+    E: Collexetable<V> + PartialEq,
+    V: FieldValue,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.collex.span() == other.collex.span()
+            && self.collex.unit() == other.collex.unit()
+            // 未建索引的一方 id_map 为空，只比较元素
+            && (!(self.indexed && other.indexed) || self.id_map == other.id_map)
+            && self.collex.iter().eq(other.collex.iter())
     }
 }
 
+impl<K,E,V> Eq for OrdIdMap<K,E,V>
+where
+    K: Id,
+    E: Collexetable<V> + Eq,
+    V: FieldValue + Eq,
+{}
 
 impl<K, E, V> OrdIdMap<K, E, V>
 where
@@ -87,6 +335,11 @@ where
         Ok(Self{
             id_map: IdMap::with_id(),
             collex: FieldCollex::new(span, unit)?,
+            changes: ChangeLog::default(),
+            next_fit: None,
+            indexed: true,
+            lazy_index: false,
+            shrink_policy: ShrinkPolicy::default(),
         })
     }
     
@@ -99,6 +352,11 @@ where
         Ok(Self{
             id_map: IdMap::with_id_capacity(capacity),
             collex: FieldCollex::with_capacity(span, unit, capacity)?,
+            changes: ChangeLog::default(),
+            next_fit: None,
+            indexed: true,
+            lazy_index: false,
+            shrink_policy: ShrinkPolicy::default(),
         })
     }
     
@@ -108,86 +366,1030 @@ where
         vec: Vec<E>,
     ) -> Result<Self, WithElementsFieldCollexError<V>>
     {
-        let mut id_map = IdMap::with_id();
-        let other = extend_from_vec(&mut id_map, vec);
+        let mut id_map = IdMap::with_id_capacity(vec.len());
+        let other = extend_from_iter(&mut id_map, vec, true);
         
         Ok(Self{
             id_map,
             collex: FieldCollex::with_elements(span, unit, other)?,
+            changes: ChangeLog::default(),
+            next_fit: None,
+            indexed: true,
+            lazy_index: false,
+            shrink_policy: ShrinkPolicy::default(),
         })
     }
     
-    pub fn extend(&mut self, vec: Vec<E>) {
-        let other = extend_from_vec(&mut self.id_map, vec);
-        self.collex.extend(other)
+    /// 以任意迭代器的元素构造
+    pub fn from_iter_with<I>(
+        span: Span<V>,
+        unit: V,
+        iter: I,
+    ) -> Result<Self, WithElementsFieldCollexError<V>>
+    where
+        I: IntoIterator<Item = E>,
+    {
+        Self::with_elements(span, unit, iter.into_iter().collect())
+    }
+    
+    /// 逐个分配 Id 后整批送入 collex（`FieldCollex::extend` 只接受 Vec）
+    ///
+    /// 只有确实进入 collex 的元素才登记到 id_map，被拒绝的元素的 Id 不会回收
+    pub fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = E>,
+    {
+        let pairs: Vec<Pair<K, E>> = pair_up(&mut self.id_map, iter, false).collect();
+        let ids: Vec<(K, V)> = pairs.iter().map(|p| (p.0, p.collexate())).collect();
+        self.reserve(pairs.len());
+        self.collex.extend(pairs);
+        self.record_inserted(ids);
+        self.debug_check_invariants(None);
+    }
+    
+    /// 大批量插入：按 field 值排序一次，在一趟扫描中检查 span 与冲突，再把合格的元素整批送入 collex
+    ///
+    /// Id 按排序后的顺序分配，被拒绝的元素的 Id 不会回收。
+    /// 越界或与已有元素、同批中较早的元素冲突的元素连同原因按 field 顺序返回，其余元素均已插入
+    pub fn extend_sorted(&mut self, items: Vec<E>) -> Result<Vec<K>, ImportError<K, E>> {
+        let mut keyed: Vec<(V, E)> = items.into_iter().map(|e| (e.collexate(), e)).collect();
+        keyed.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        
+        let span = self.collex.span();
+        let mut accepted = Vec::with_capacity(keyed.len());
+        let mut rejected = Vec::new();
+        let mut last = None;
+        for (v, elem) in keyed {
+            let id = self.id_map.next_id();
+            if !span.contains(&v) {
+                rejected.push((id, ImportRejection::OutOfSpan(elem)));
+            } else if last == Some(v) || self.collex.get(v).is_some() {
+                rejected.push((id, ImportRejection::AlreadyExist(elem)));
+            } else {
+                last = Some(v);
+                accepted.push(Pair(id, elem));
+            }
+        }
+        
+        self.reserve(accepted.len());
+        let placed: Vec<(K, V)> = accepted.iter().map(|p| (p.0, *p.collexate_ref())).collect();
+        self.collex.extend(accepted);
+        let mut ids = Vec::with_capacity(placed.len());
+        for (id, v) in placed {
+            self.set_index(id, v);
+            self.changes.record(id, ChangeKind::Inserted);
+            ids.push(id);
+        }
+        self.debug_check_invariants(None);
+        
+        if rejected.is_empty() {
+            Ok(ids)
+        } else {
+            Err(ImportError { rejected })
+        }
+    }
+    
+    /// 同 `extend`，被拒绝的元素随结果返回；只有进入 collex 的元素才登记到 id_map
+    pub fn try_extend<I>(&mut self, iter: I) -> TryExtendResult<Pair<K, E>>
+    where
+        I: IntoIterator<Item = E>,
+    {
+        let other = extend_from_iter(&mut self.id_map, iter, false);
+        self.reserve(other.len());
+        let ids: Vec<(K, V)> = other.iter().map(|p| (p.0, *p.collexate_ref())).collect();
+        let result = self.collex.try_extend(other);
+        self.record_inserted(ids);
+        self.debug_check_invariants(None);
+        result
     }
     
-    pub fn try_extend(&mut self, vec: Vec<E>) -> TryExtendResult<Pair<K, E>> {
-        let other = extend_from_vec(&mut self.id_map, vec);
-        self.collex.try_extend(other)
+    /// 把批量插入中确实进入 collex 的 Id 登记到 id_map 并记录变更，`ids` 为 `(Id, field 值)`
+    fn record_inserted(&mut self, ids: Vec<(K, V)>) {
+        for (id, v) in ids {
+            let placed = self.collex
+                .get(v)
+                .is_some_and(|p| p.0 == id);
+            if placed {
+                self.set_index(id, v);
+                self.changes.record(id, ChangeKind::Inserted);
+            }
+        }
     }
     
+    /// 先放入 collex，成功后再登记到 id_map：成功时只哈希一次，失败时不触碰 id_map（Id 不回收）
     pub fn insert(&mut self, elem: E) -> Result<K, InsertFieldCollexError<E>> {
         use InsertFieldCollexError::*;
-        let obj = insert(&mut self.id_map, elem);
-        let id = obj.0;
-        self.collex.insert(obj)
-            .map(|_| id)
-            .map_err(|err|
-                {
-                    self.id_map.remove(id);
-                    match err {
-                        OutOfSpan(o) => { OutOfSpan(o.1) }
-                        AlreadyExist(o) => { AlreadyExist(o.1) }
+        let id = self.id_map.next_id();
+        let v = elem.collexate();
+        match self.collex.insert(Pair(id, elem)) {
+            Ok(_) => {
+                self.set_index(id, v);
+                self.changes.record(id, ChangeKind::Inserted);
+                self.debug_check_invariants(Some(id));
+                Ok(id)
+            }
+            Err(OutOfSpan(o)) => Err(OutOfSpan(o.1)),
+            Err(AlreadyExist(o)) => Err(AlreadyExist(o.1)),
+        }
+    }
+    
+    /// 【手动指定 Id】插入元素，返回该 Id 下的旧元素（若存在）
+    ///
+    /// 若 Id 大于当前 max_id，会同步更新 max_id；插入失败时旧元素保持原样。
+    /// 对 id_map 的查询与更新经同一个 entry 完成，只哈希一次
+    pub fn insert_with_id(&mut self, id: K, elem: E) -> Result<Option<E>, InsertFieldCollexError<E>> {
+        use InsertFieldCollexError::*;
+        self.ensure_index();
+        if !self.indexed {
+            return self.insert_with_id_unindexed(id, elem);
+        }
+        let v = elem.collexate();
+        let result = match self.id_map.entry(id) {
+            MapEntry::Occupied(mut slot) => {
+                let at = *slot.get();
+                // id_map 与 collex 不一致时不去删除其他 Id 的元素
+                let old = if self.collex.get(at).is_some_and(|p| p.0 == id) {
+                    self.collex.remove(at).ok()
+                } else {
+                    None
+                };
+                match self.collex.insert(Pair(id, elem)) {
+                    Ok(_) => {
+                        slot.insert(v);
+                        let kind = if old.is_some() { ChangeKind::Modified } else { ChangeKind::Inserted };
+                        self.changes.record(id, kind);
+                        Ok(old.map(|p| p.1))
+                    }
+                    Err(err) => {
+                        if let Some(old) = old {
+                            let _ = self.collex.insert(old);
+                        }
+                        Err(err)
                     }
                 }
-            )
+            }
+            MapEntry::Vacant(slot) => match self.collex.insert(Pair(id, elem)) {
+                Ok(_) => {
+                    slot.insert(v);
+                    self.changes.record(id, ChangeKind::Inserted);
+                    Ok(None)
+                }
+                Err(err) => Err(err),
+            },
+        };
+        match result {
+            Ok(old) => {
+                self.id_map.bump_max_id(id);
+                self.debug_check_invariants(Some(id));
+                Ok(old)
+            }
+            Err(OutOfSpan(o)) => Err(OutOfSpan(o.1)),
+            Err(AlreadyExist(o)) => Err(AlreadyExist(o.1)),
+        }
+    }
+    
+    fn insert_with_id_unindexed(&mut self, id: K, elem: E) -> Result<Option<E>, InsertFieldCollexError<E>> {
+        use InsertFieldCollexError::*;
+        let old = scan_id(&self.collex, id).and_then(|v| self.collex.remove(v).ok());
+        match self.collex.insert(Pair(id, elem)) {
+            Ok(_) => {
+                self.id_map.bump_max_id(id);
+                let kind = if old.is_some() { ChangeKind::Modified } else { ChangeKind::Inserted };
+                self.changes.record(id, kind);
+                self.debug_check_invariants(Some(id));
+                Ok(old.map(|p| p.1))
+            }
+            Err(err) => {
+                if let Some(old) = old {
+                    let _ = self.collex.insert(old);
+                }
+                Err(match err {
+                    OutOfSpan(o) => OutOfSpan(o.1),
+                    AlreadyExist(o) => AlreadyExist(o.1),
+                })
+            }
+        }
+    }
+    
+    /// 以调用者指定的 Id 批量导入元素，用于从其他系统恢复状态
+    ///
+    /// Id 已存在、越界或 field 值冲突的元素被拒绝并随错误返回，其余照常导入；max_id 随导入的 Id 更新
+    pub fn import_with_ids<I>(&mut self, pairs: I) -> Result<(), ImportError<K, E>>
+    where
+        I: IntoIterator<Item = (K, E)>,
+    {
+        use InsertFieldCollexError::*;
+        let mut rejected = Vec::new();
+        for (id, elem) in pairs {
+            if self.id_map.contains_id(id) {
+                rejected.push((id, ImportRejection::DuplicateId(elem)));
+                continue;
+            }
+            match self.insert_with_id(id, elem) {
+                Ok(_) => {}
+                Err(OutOfSpan(e)) => rejected.push((id, ImportRejection::OutOfSpan(e))),
+                Err(AlreadyExist(e)) => rejected.push((id, ImportRejection::AlreadyExist(e))),
+            }
+        }
+        if rejected.is_empty() { Ok(()) } else { Err(ImportError { rejected }) }
+    }
+    
+    /// 以新元素替换 Id 对应的元素，Id 不变，返回旧元素
+    ///
+    /// 新元素不满足 span 或与其他元素冲突时，旧元素保持原样
+    pub fn replace(&mut self, id: K, new: E) -> Result<E, ReplaceError<E>> {
+        self.ensure_index();
+        let Some(v) = self.locate(id) else {
+            return Err(ReplaceError::CannotFind(new));
+        };
+        let Ok(old) = self.collex.remove(v) else {
+            return Err(ReplaceError::CannotFind(new));
+        };
+        let new_v = new.collexate();
+        match self.collex.insert(Pair(id, new)) {
+            Ok(_) => {
+                self.set_index(id, new_v);
+                self.changes.record(id, ChangeKind::Modified);
+                self.debug_check_invariants(Some(id));
+                Ok(old.1)
+            }
+            Err(err) => {
+                let _ = self.collex.insert(old);
+                Err(match err {
+                    InsertFieldCollexError::OutOfSpan(o) => { ReplaceError::OutOfSpan(o.1) }
+                    InsertFieldCollexError::AlreadyExist(o) => { ReplaceError::AlreadyExist(o.1) }
+                })
+            }
+        }
+    }
+    
+    /// 交换两个 Id 所指向的元素，元素在 field 中的位置不变
+    pub fn swap(&mut self, a: K, b: K) -> Result<(), SwapError<K>> {
+        use SwapError::*;
+        self.ensure_index();
+        let va = self.locate(a).ok_or(CannotFind(a))?;
+        let vb = self.locate(b).ok_or(CannotFind(b))?;
+        if a == b {
+            return Ok(());
+        }
+        
+        let mut pa = self.collex.remove(va).map_err(|_| CannotFind(a))?;
+        let Ok(mut pb) = self.collex.remove(vb) else {
+            let _ = self.collex.insert(pa);
+            return Err(CannotFind(b));
+        };
+        pa.0 = b;
+        pb.0 = a;
+        // 两个位置刚刚腾出，放回必然成功
+        let _ = self.collex.insert(pa);
+        let _ = self.collex.insert(pb);
+        self.set_index(a, vb);
+        self.set_index(b, va);
+        self.changes.record(a, ChangeKind::Modified);
+        self.changes.record(b, ChangeKind::Modified);
+        self.debug_check_invariants(Some(a));
+        Ok(())
     }
     
+    /// 删除 Id 对应的元素；id_map 与 collex 不一致时返回 None 而不改动任何内容，需要区分时用 `try_remove`
     pub fn remove(&mut self, id: K) -> Option<E> {
-        let v = self.id_map.remove(id)?;
+        self.try_remove(id).unwrap_or_default()
+    }
+    
+    /// 删除 Id 对应的元素，id_map 与 collex 不一致时返回错误，不改动任何内容
+    pub fn try_remove(&mut self, id: K) -> Result<Option<E>, InconsistentState<K, V>> {
+        self.ensure_index();
+        let Some(v) = self.locate_checked(id)? else {
+            return Ok(None);
+        };
+        if self.indexed {
+            self.id_map.remove(id);
+        }
+        self.changes.record(id, ChangeKind::Removed);
         
-        Some(self.collex
+        let elem = self.collex
             .remove(v)
-            .unwrap()
-            .1)
+            .ok()
+            .map(|p| p.1);
+        self.apply_shrink_policy();
+        self.debug_check_invariants(Some(id));
+        Ok(elem)
+    }
+    
+    /// 批量删除，按传入顺序返回每个 Id 及其被删除的元素（不存在或重复出现的 Id 为 None）
+    ///
+    /// 先一次定位所有 Id，再从 collex 取出元素，最后一次性从 id_map 移除；收缩与校验只在末尾做一次
+    pub fn remove_many<I>(&mut self, ids: I) -> Vec<(K, Option<E>)>
+    where
+        I: IntoIterator<Item = K>,
+    {
+        let ids = ids.into_iter();
+        self.ensure_index();
+        let mut seen = HashSet::with_capacity(ids.size_hint().0);
+        let located: Vec<(K, Option<V>)> = ids
+            .map(|id| {
+                let v = if seen.insert(id.as_u64()) {
+                    // id_map 与 collex 不一致的 Id 不去删除其他 Id 的元素
+                    self.locate_checked(id).ok().flatten()
+                } else {
+                    None
+                };
+                (id, v)
+            })
+            .collect();
+        
+        let mut gone = Vec::with_capacity(located.len());
+        let removed: Vec<(K, Option<E>)> = located
+            .into_iter()
+            .map(|(id, v)| {
+                let elem = v
+                    .and_then(|v| self.collex.remove(v).ok())
+                    .map(|p| p.1);
+                if elem.is_some() {
+                    gone.push(id);
+                    self.changes.record(id, ChangeKind::Removed);
+                }
+                (id, elem)
+            })
+            .collect();
+        if self.indexed {
+            self.id_map.remove_all(&gone);
+        }
+        self.apply_shrink_policy();
+        self.debug_check_invariants(None);
+        removed
+    }
+    
+    /// 删除并返回 field 值最小的元素
+    pub fn pop_min(&mut self) -> Option<(K, E)> {
+        let (id, v) = self.collex
+            .iter()
+            .next()
+            .map(|p| (p.0, p.collexate()))?;
+        self.pop_at(id, v)
+    }
+    
+    /// 删除并返回 field 值最大的元素
+    pub fn pop_max(&mut self) -> Option<(K, E)> {
+        let (id, v) = self.collex
+            .iter()
+            .last()
+            .map(|p| (p.0, p.collexate()))?;
+        self.pop_at(id, v)
+    }
+    
+    fn pop_at(&mut self, id: K, v: V) -> Option<(K, E)> {
+        self.id_map.remove(id);
+        self.changes.record(id, ChangeKind::Removed);
+        let popped = self.collex.remove(v).ok().map(|p| (p.0, p.1));
+        self.apply_shrink_policy();
+        self.debug_check_invariants(Some(id));
+        popped
+    }
+    
+    /// 仅保留满足条件的元素
+    ///
+    /// 单趟遍历：按 field 顺序取出所有元素，只把保留的放回
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(K, &E) -> bool,
+    {
+        let dropped = rebuild_collex(&mut self.collex, |p| f(p.0, &p.1));
+        for p in dropped {
+            self.id_map.remove(p.0);
+            self.changes.record(p.0, ChangeKind::Removed);
+        }
+        self.apply_shrink_policy();
+        self.debug_check_invariants(None);
+    }
+    
+    /// 取出所有元素，按 field 顺序产出 `(K, E)`
+    ///
+    /// id_map 的 max_id 保持不变，之后插入的元素不会复用旧 Id
+    pub fn drain(&mut self) -> impl Iterator<Item = (K, E)> {
+        let values: Vec<V> = self.collex
+            .iter()
+            .map(|p| p.collexate())
+            .collect();
+        let drained: Vec<(K, E)> = values
+            .into_iter()
+            .filter_map(|v| self.collex.remove(v).ok())
+            .map(|p| (p.0, p.1))
+            .collect();
+        self.id_map.clear();
+        for (id, _) in &drained {
+            self.changes.record(*id, ChangeKind::Removed);
+        }
+        self.apply_shrink_policy();
+        self.debug_check_invariants(None);
+        drained.into_iter()
+    }
+    
+    /// 清空所有元素（保留 span、unit 与 max_id）
+    pub fn clear(&mut self) {
+        let values: Vec<V> = self.collex
+            .iter()
+            .map(|p| p.collexate())
+            .collect();
+        for v in values {
+            if let Ok(p) = self.collex.remove(v) {
+                self.changes.record(p.0, ChangeKind::Removed);
+            }
+        }
+        self.id_map.clear();
+        self.apply_shrink_policy();
+        self.debug_check_invariants(None);
     }
     
     pub fn modify<F,R>(&mut self, id: K, f: F) -> Result<R, ModifyFieldCollexError<(R,E)>>
     where
-        F: Fn(&mut E) -> R,
+        F: FnOnce(&mut E) -> R,
     {
         use ModifyFieldCollexError::*;
-        let v = self.id_map.get(id).ok_or(CannotFind)?;
+        self.ensure_index();
+        // 只查一次 id_map，拿到的位置在修改成功后原地更新；未建索引时扫描 collex
+        let (v, slot) = if self.indexed {
+            let slot = self.id_map.get_mut(id).ok_or(CannotFind)?;
+            (*slot, Some(slot))
+        } else {
+            (scan_id(&self.collex, id).ok_or(CannotFind)?, None)
+        };
+        // id_map 与 collex 不一致时不去修改其他 Id 的元素
+        if !self.collex.get(v).is_some_and(|p| p.0 == id) {
+            return Err(CannotFind);
+        }
+        // FieldCollex 只接受 Fn，借 Cell 让 FnOnce 只被取出调用一次
+        let f = Cell::new(Some(f));
         let (r,new_v) =
             self.collex
-                .modify(*v,|e| (f(e),e.collexate()) )
+                .modify(v,|e| (f.take().unwrap()(e),e.collexate()) )
                 .map_err(|err|
                     err.map(|e| (e.0.0, e.1.1))
                 )?;
-        *self.id_map.get_mut(id).unwrap() = new_v;
+        if let Some(slot) = slot {
+            *slot = new_v;
+        }
+        self.changes.record(id, ChangeKind::Modified);
+        self.debug_check_invariants(Some(id));
         Ok(r)
     }
     
     pub fn try_modify<F,R>(&mut self, id: K, f: F) -> Result<R, ModifyFieldCollexError<R>>
     where
-        F: Fn(&mut E) -> R,
+        F: FnOnce(&mut E) -> R,
     {
         use ModifyFieldCollexError::*;
-        let v = self.id_map.get(id).ok_or(CannotFind)?;
+        self.ensure_index();
+        let (v, slot) = if self.indexed {
+            let slot = self.id_map.get_mut(id).ok_or(CannotFind)?;
+            (*slot, Some(slot))
+        } else {
+            (scan_id(&self.collex, id).ok_or(CannotFind)?, None)
+        };
+        // id_map 与 collex 不一致时不去修改其他 Id 的元素
+        if !self.collex.get(v).is_some_and(|p| p.0 == id) {
+            return Err(CannotFind);
+        }
+        let f = Cell::new(Some(f));
         let (r,new_v) =
             self.collex
-                .try_modify(*v, |e| (f(e),e.collexate()) )
+                .try_modify(v, |e| (f.take().unwrap()(e),e.collexate()) )
                 .map_err(|err|
                      err.map(|e| e.0)
                 )?;
-        *self.id_map.get_mut(id).unwrap() = new_v;
+        if let Some(slot) = slot {
+            *slot = new_v;
+        }
+        self.changes.record(id, ChangeKind::Modified);
+        self.debug_check_invariants(Some(id));
         Ok(r)
     }
     
+    /// 批量修改：先对所有 Id 执行闭包，最后统一重新 collexate 并校验
+    ///
+    /// 重复的 Id 只处理一次；失败的 Id 连同原因一并返回，其元素保持修改前的值
+    pub fn modify_many<F>(&mut self, ids: &[K], mut f: F) -> Result<(), Vec<(K, ModifyManyError)>>
+    where
+        F: FnMut(K, &mut E),
+    {
+        self.ensure_index();
+        let mut errors = Vec::new();
+        let mut seen = HashSet::with_capacity(ids.len());
+        let mut pending = Vec::with_capacity(ids.len());
+        for &id in ids {
+            if !seen.insert(id.as_u64()) {
+                continue;
+            }
+            let taken = self.locate(id)
+                .and_then(|v| self.collex.remove(v).ok().map(|p| (v, p)));
+            match taken {
+                Some((v, mut pair)) => {
+                    f(id, &mut pair.1);
+                    pending.push((v, pair));
+                }
+                None => errors.push((id, ModifyManyError::CannotFind)),
+            }
+        }
+        
+        errors.extend(guard::write_back(self, pending));
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+    
+    /// 按 Id 查询；id_map 与 collex 不一致时返回 None，需要区分时用 `try_get_with_id`
     pub fn get_with_id(&self, id: K) -> Option<&E> {
-        let v = self.id_map.get(id)?;
-        self.collex.get(*v).map(|v| &v.1)
+        self.try_get_with_id(id).unwrap_or_default()
+    }
+    
+    /// 按 Id 查询，id_map 与 collex 不一致时返回错误
+    pub fn try_get_with_id(&self, id: K) -> Result<Option<&E>, InconsistentState<K, V>> {
+        let Some(v) = self.locate_checked(id)? else {
+            return Ok(None);
+        };
+        Ok(self.collex.get(v).map(|p| &p.1))
+    }
+    
+    /// 按 field 值反查 Id
+    pub fn id_at(&self, value: V) -> Option<K> {
+        self.collex.get(value).map(|p| p.0)
+    }
+    
+    /// 按 field 顺序找出所有满足条件的元素的 Id
+    pub fn find_ids<F>(&self, pred: F) -> Vec<K>
+    where
+        F: Fn(&E) -> bool,
+    {
+        self.collex
+            .iter()
+            .filter(|p| pred(&p.1))
+            .map(|p| p.0)
+            .collect()
+    }
+    
+    /// 按 field 顺序遍历 field 值落在 `range` 内的 `(K, &E)`
+    ///
+    /// 从头跳过 `range` 之前的元素，越过 `range` 后立即停止。
+    /// field-collex 0.0.10 的 `find_ge` / `find_gt` 在目标块之后紧跟空块时会 panic，暂不经其按值定位
+    pub fn ids_in_range(&self, range: Span<V>) -> impl Iterator<Item = (K, &E)> {
+        let start = range.clone();
+        self.collex
+            .iter()
+            .skip_while(move |p| !start.contains(p.collexate_ref()))
+            .take_while(move |p| range.contains(p.collexate_ref()))
+            .map(|p| (p.0, &p.1))
+    }
+    
+    /// field 值最接近 `value` 的元素，距离相同时取较小者
+    pub fn nearest(&self, value: V) -> Option<(K, &E)> {
+        match (self.nearest_below(value), self.nearest_above(value)) {
+            (Some(below), Some(above)) => {
+                if value - *below.1.collexate_ref() <= *above.1.collexate_ref() - value {
+                    Some(below)
+                } else {
+                    Some(above)
+                }
+            }
+            (below, above) => below.or(above),
+        }
+    }
+    
+    /// field 值大于等于 `value` 的元素中最小的一个
+    ///
+    /// 按 field 顺序扫描，遇到第一个满足的元素即停止；
+    /// field-collex 0.0.10 的 `find_ge` 在目标块之后紧跟空块时会 panic，暂不使用
+    pub fn nearest_above(&self, value: V) -> Option<(K, &E)> {
+        self.collex
+            .iter()
+            .find(|p| *p.collexate_ref() >= value)
+            .map(|p| (p.0, &p.1))
+    }
+    
+    /// field 值小于等于 `value` 的元素中最大的一个
+    ///
+    /// 按 field 顺序扫描，越过 `value` 即停止；`find_le` 的问题同 `nearest_above`
+    pub fn nearest_below(&self, value: V) -> Option<(K, &E)> {
+        self.collex
+            .iter()
+            .take_while(|p| *p.collexate_ref() <= value)
+            .last()
+            .map(|p| (p.0, &p.1))
+    }
+    
+    /// 按 Id 获取元素的可变访问守卫
+    ///
+    /// 守卫释放时重新 collexate，并同步 collex 中的位置与 id_map 中的条目
+    ///
+    /// 守卫被泄漏（如 `mem::forget`）时元素随之丢失，见 [`ModifyGuard`]
+    pub fn get_mut_with_id(&mut self, id: K) -> Option<ModifyGuard<'_, K, E, V>> {
+        self.ensure_index();
+        let v = self.locate(id)?;
+        let pair = self.collex.remove(v).ok()?;
+        Some(ModifyGuard::new(pair, v, WriteBack::new(self)))
+    }
+    
+    /// 同时获取多个互不相同的 Id 的可变访问守卫
+    ///
+    /// 任一 Id 不存在或 Id 有重复时返回 None；所有守卫释放后统一写回
+    pub fn get_disjoint_mut<const N: usize>(
+        &mut self,
+        ids: [K; N],
+    ) -> Option<[ModifyGuard<'_, K, E, V>; N]> {
+        self.ensure_index();
+        for (i, id) in ids.iter().enumerate() {
+            if ids[..i].contains(id) || !self.contains_id(*id) {
+                return None;
+            }
+        }
+        
+        let taken = ids.map(|id| {
+            let v = self.locate(id).expect("checked above");
+            (v, self.collex.remove(v).ok())
+        });
+        if taken.iter().any(|(_, p)| p.is_none()) {
+            for p in taken.into_iter().filter_map(|(_, p)| p) {
+                let _ = self.collex.insert(p);
+            }
+            return None;
+        }
+        
+        let write_back = WriteBack::new(self);
+        Some(taken.map(|(v, p)| ModifyGuard::new(p.unwrap(), v, Rc::clone(&write_back))))
+    }
+    
+    /// 判断 Id 是否仍然存活
+    pub fn contains_id(&self, id: K) -> bool {
+        // id_map 与 collex 不一致时视为不存在
+        self.locate_checked(id).is_ok_and(|v| v.is_some())
+    }
+    
+    /// 按 field 顺序遍历 `(K, &E)`
+    pub fn iter(&self) -> impl Iterator<Item = (K, &E)> {
+        self.collex.iter().map(|p| (p.0, &p.1))
+    }
+    
+    /// 按 Id 查询，连同 Id 一起返回
+    pub fn get_ref(&self, id: K) -> Option<PairRef<'_, K, E>> {
+        let v = self.locate(id)?;
+        self.collex.get(v).map(PairRef::from)
+    }
+    
+    /// 按 field 顺序遍历 `PairRef`
+    pub fn iter_refs(&self) -> impl Iterator<Item = PairRef<'_, K, E>> {
+        self.collex.iter().map(PairRef::from)
+    }
+    
+    /// 按 field 值升序遍历 `(K, &E)`
+    ///
+    /// 与 `iter` 相同，显式表明依赖 field 顺序的场景
+    pub fn iter_ordered(&self) -> impl Iterator<Item = (K, &E)> {
+        self.iter()
+    }
+    
+    /// 按 field 顺序遍历所有存活的 Id
+    pub fn ids(&self) -> impl Iterator<Item = K> {
+        self.collex.iter().map(|p| p.0)
+    }
+    
+    /// 按 field 顺序遍历所有元素
+    pub fn values(&self) -> impl Iterator<Item = &E> {
+        self.collex.iter().map(|p| &p.1)
+    }
+    
+    /// 按 field 顺序可变遍历，闭包得到 `(K, &mut E)`
+    ///
+    /// 经 collex 自身的 `try_modify` 原地修改，只有 field 值改变的元素才会重新 collexate 并同步 id_map。
+    /// 新值越界或与其他元素冲突的元素退回原值，连同原因返回
+    pub fn for_each_mut<F>(&mut self, f: F) -> Vec<(K, ModifyManyError)>
+    where
+        F: FnMut(K, &mut E),
+    {
+        use ModifyFieldCollexError::*;
+        let entries: Vec<(K, V)> = self.collex
+            .iter()
+            .map(|p| (p.0, p.collexate()))
+            .collect();
+        // FieldCollex 只接受 Fn，借 RefCell 调用 FnMut
+        let f = RefCell::new(f);
+        let mut rejected = Vec::new();
+        for (id, v) in entries {
+            let modified = self.collex.try_modify(v, |p| {
+                (f.borrow_mut())(id, &mut p.1);
+                p.collexate()
+            });
+            match modified {
+                Ok(new_v) if new_v != v => {
+                    self.set_index(id, new_v);
+                    self.changes.record(id, ChangeKind::Modified);
+                }
+                Ok(_) => {}
+                Err(CannotFind) => rejected.push((id, ModifyManyError::CannotFind)),
+                Err(InsertError(InsertFieldCollexError::OutOfSpan(_))) => rejected.push((id, ModifyManyError::OutOfSpan)),
+                Err(InsertError(InsertFieldCollexError::AlreadyExist(_))) => rejected.push((id, ModifyManyError::AlreadyExist)),
+            }
+        }
+        self.debug_check_invariants(None);
+        rejected
+    }
+    
+    /// 元素数量
+    ///
+    /// 经 Deref 得到的 `FieldCollex::len` 是块数（含空块），不等于元素数量
+    pub fn len(&self) -> usize {
+        if self.indexed {
+            self.id_map.len()
+        } else {
+            self.collex.iter().count()
+        }
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.collex.is_empty()
+    }
+    
+    /// 不重新分配即可容纳的元素数量（取 id_map 与 collex 中较小者；未建索引时只看 collex）
+    pub fn capacity(&self) -> usize {
+        if self.indexed {
+            self.id_map.capacity().min(self.collex.capacity())
+        } else {
+            self.collex.capacity()
+        }
+    }
+    
+    /// 为 id_map 预留至少可再容纳 additional 个元素的空间
+    ///
+    /// FieldCollex 按块分配且没有 reserve，collex 的容量只随插入增长，不受此影响
+    pub fn reserve(&mut self, additional: usize) {
+        if self.indexed {
+            self.id_map.reserve(additional);
+        }
+    }
+    
+    /// 同时释放 id_map 与 collex 多余的容量
+    ///
+    /// FieldCollex 没有 shrink_to_fit，collex 需取出全部元素后重建，耗时 O(n)
+    pub fn shrink_to_fit(&mut self) {
+        self.id_map.shrink_to_fit();
+        let span = self.collex.span().clone();
+        let unit = *self.collex.unit();
+        let Ok(empty) = FieldCollex::new(span.clone(), unit) else {
+            unreachable!("span and unit come from an existing collex")
+        };
+        let pairs: Vec<Pair<K,E>> = std::mem::replace(&mut self.collex, empty).into_iter().collect();
+        let Ok(collex) = FieldCollex::with_elements(span, unit, pairs) else {
+            unreachable!("span and unit come from an existing collex")
+        };
+        self.collex = collex;
+    }
+    
+    /// 把另一个 OrdIdMap 的元素全部并入
+    ///
+    /// 不超过本表 max_id 的 Id 都曾由本表分配过（可能已被删除），这些元素一律分配新 Id，
+    /// 保证已删除的 Id 不被复用；越界或 field 值冲突的元素不会并入，随报告返回
+    pub fn merge(&mut self, other: Self) -> MergeReport<K, E> {
+        use InsertFieldCollexError::*;
+        let mut report = MergeReport {
+            remapped: Vec::new(),
+            rejected: Vec::new(),
+        };
+        let limit = self.id_map.max_id().as_u64();
+        let pairs: Vec<Pair<K, E>> = other.into_iter().collect();
+        // 先越过 other 的所有 Id，重新分配的 Id 不会与 other 中其余元素的 Id 相撞
+        for p in &pairs {
+            self.id_map.bump_max_id(p.0);
+        }
+        for Pair(old_id, elem) in pairs {
+            let v = elem.collexate();
+            let id = if old_id.as_u64() <= limit {
+                self.id_map.next_id()
+            } else {
+                old_id
+            };
+            self.set_index(id, v);
+            match self.collex.insert(Pair(id, elem)) {
+                Ok(_) => {
+                    self.changes.record(id, ChangeKind::Inserted);
+                    if id != old_id {
+                        report.remapped.push((old_id, id));
+                    }
+                }
+                Err(OutOfSpan(p) | AlreadyExist(p)) => {
+                    self.id_map.remove(id);
+                    report.rejected.push((old_id, p.1));
+                }
+            }
+        }
+        self.debug_check_invariants(None);
+        report
+    }
+    
+    /// 按条件把元素拆分到两个新的 OrdIdMap（满足条件的在前），span、unit 与 Id 均保持不变
+    ///
+    /// 两者都继承原 max_id，新分配的 Id 不会与拆分前的任何 Id 重复；但两者各自从 max_id 之后分配，
+    /// 之后插入的元素可能在两边得到相同的 Id。需要互不重复时由调用者协调，
+    /// 例如由一个 `IdGen` 统一领取 Id 再经 `insert_with_id` 插入
+    pub fn split_by<F>(self, f: F) -> (Self, Self)
+    where
+        F: Fn(K, &E) -> bool,
+    {
+        let span = self.collex.span().clone();
+        let unit = *self.collex.unit();
+        let max_id = self.id_map.max_id();
+        let indexed = self.indexed;
+        let (left, right): (Vec<_>, Vec<_>) = self
+            .into_iter()
+            .partition(|p| f(p.0, &p.1));
+        let mut left = Self::from_pairs(span.clone(), unit, left, max_id);
+        let mut right = Self::from_pairs(span, unit, right, max_id);
+        if !indexed {
+            left.drop_index();
+            right.drop_index();
+        }
+        (left, right)
+    }
+    
+    /// 由原本就合法的元素重建，用于拆分等内部场景
+    fn from_pairs(span: Span<V>, unit: V, pairs: Vec<Pair<K,E>>, max_id: K) -> Self {
+        let mut id_map = IdMap::with_id_capacity(pairs.len());
+        for p in &pairs {
+            id_map.insert_with_id(p.0, p.collexate());
+        }
+        id_map.bump_max_id(max_id);
+        let Ok(collex) = FieldCollex::with_elements(span, unit, pairs) else {
+            unreachable!("elements taken from a valid collex")
+        };
+        Self {
+            id_map,
+            collex,
+            changes: ChangeLog::default(),
+            next_fit: None,
+            indexed: true,
+            lazy_index: false,
+            shrink_policy: ShrinkPolicy::default(),
+        }
+    }
+    
+    /// 迄今分配过的最大 Id（含已删除元素的 Id）
+    pub fn max_id(&self) -> K {
+        self.id_map.max_id()
+    }
+    
+    /// 下一次 `insert` 将分配的 Id，不插入任何元素
+    ///
+    /// 可在插入前预先构造指向该元素的引用；其间若有其他插入（包括失败的插入）或经 `IdGen` 领取 Id，预测即失效。
+    /// Id 已耗尽时返回 None
+    pub fn peek_next_id(&self) -> Option<K> {
+        self.id_map.peek_next_id()
+    }
+    
+    /// Id 到 field 值的索引，只读
+    pub fn id_map(&self) -> &IdMap<K,V> {
+        &self.id_map
+    }
+    
+    /// 底层 collex，只读；需要直接修改时用 `collex_mut_guard`
+    pub fn collex(&self) -> &FieldCollex<Pair<K,E>,V> {
+        &self.collex
+    }
+    
+    pub fn span(&self) -> &Span<V> {
+        self.collex.span()
+    }
+    
+    pub fn unit(&self) -> &V {
+        self.collex.unit()
+    }
+    
+    /// 更换 span：先校验所有元素都能放入新 span，全部成功才提交
+    ///
+    /// 失败时返回无法放入的元素的 Id 与 field 值，原有数据保持不变
+    pub fn resize_span(&mut self, new_span: Span<V>) -> Result<(), ResizeSpanError<K, V>> {
+        let mut collex = FieldCollex::new(new_span, *self.collex.unit())
+            .map_err(ResizeSpanError::Invalid)?;
+        let offending: Vec<(K, V)> = self.collex
+            .iter()
+            .filter(|p| !collex.span().contains(p.collexate_ref()))
+            .map(|p| (p.0, p.collexate()))
+            .collect();
+        if !offending.is_empty() {
+            return Err(ResizeSpanError::OutOfSpan(offending));
+        }
+        
+        let mut rejected = Vec::new();
+        move_all(&mut self.collex, &mut collex, |p| rejected.push((p.0, p.collexate())));
+        if !rejected.is_empty() {
+            // 回滚：已迁移的元素全部放回原 collex
+            move_all(&mut collex, &mut self.collex, |_| {});
+            return Err(ResizeSpanError::OutOfSpan(rejected));
+        }
+        self.collex = collex;
+        Ok(())
+    }
+    
+    /// 更换 unit，并按新 unit 重新校验所有元素，全部通过才提交
+    ///
+    /// 失败时返回违反新 unit 的元素的 Id 与 field 值，原有数据保持不变
+    pub fn set_unit(&mut self, unit: V) -> Result<(), SetUnitError<K, V>> {
+        let mut collex = FieldCollex::new(self.collex.span().clone(), unit)
+            .map_err(SetUnitError::Invalid)?;
+        
+        let mut violations = Vec::new();
+        move_all(&mut self.collex, &mut collex, |p| violations.push((p.0, p.collexate())));
+        if !violations.is_empty() {
+            move_all(&mut collex, &mut self.collex, |_| {});
+            return Err(SetUnitError::Violations(violations));
+        }
+        self.collex = collex;
+        Ok(())
+    }
+    
+    /// 交叉校验 id_map 与 collex，返回发现的所有不一致
+    pub fn validate(&self) -> Result<(), Vec<ConsistencyError<K, V>>> {
+        use ConsistencyError::*;
+        let mut errors = Vec::new();
+        let mut seen = HashSet::with_capacity(self.id_map.len());
+        
+        for p in self.collex.iter() {
+            let (id, actual) = (p.0, p.collexate());
+            if !seen.insert(id.as_u64()) {
+                errors.push(DuplicateId { id, value: actual });
+                continue;
+            }
+            if !self.indexed {
+                continue;
+            }
+            match self.id_map.get(id) {
+                None => errors.push(Orphan { id, value: actual }),
+                Some(&recorded) if recorded != actual => {
+                    errors.push(ValueMismatch { id, recorded, actual })
+                }
+                Some(_) => {}
+            }
+        }
+        
+        for (id, &value) in self.id_map.iter() {
+            if seen.contains(&id.as_u64()) {
+                continue;
+            }
+            match self.collex.get(value) {
+                None => errors.push(Missing { id, value }),
+                Some(p) => errors.push(WrongId { id, value, found: p.0 }),
+            }
+        }
+        
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+    
+    /// 丢弃 id_map，按 collex 中元素自带的 Id 重新构建
+    ///
+    /// 用于直接改动 collex 之后恢复一致；max_id 取重建结果与原值中较大者，保证旧 Id 不被复用
+    ///
+    /// 未建索引时只按 collex 中的 Id 推进 max_id，不登记条目
+    pub fn rebuild_index(&mut self) {
+        if !self.indexed {
+            for p in self.collex.iter() {
+                self.id_map.bump_max_id(p.0);
+            }
+            return;
+        }
+        let max_id = self.id_map.max_id();
+        self.id_map = build_index(self.collex.iter(), self.collex.len());
+        self.id_map.bump_max_id(max_id);
+    }
+    
+    /// 直接可变访问底层 collex，守卫释放时按 collex 重建 id_map 并记录变更
+    pub fn collex_mut_guard(&mut self) -> CollexGuard<'_, K, E, V> {
+        CollexGuard::new(self)
+    }
+    
+    /// 直接可变访问底层 collex，不做任何同步
+    ///
+    /// # Safety
+    ///
+    /// 调用者必须保证改动后 collex 中每个元素的 Id 与 field 值都与 id_map 一致，
+    /// 或随后调用 `rebuild_index`；否则所有基于 Id 的接口都会得到错误结果。
+    pub unsafe fn collex_mut_unchecked(&mut self) -> &mut FieldCollex<Pair<K,E>,V> {
+        &mut self.collex
+    }
+    
+    /// 当前版本号，每次插入、删除、修改都会使其递增
+    pub fn version(&self) -> u64 {
+        self.changes.version()
+    }
+    
+    /// 版本号 `version` 之后发生过变更的 Id 及最后一次变更的类型
+    pub fn changed_since(&self, version: u64) -> impl Iterator<Item = (K, ChangeKind)> {
+        self.changes.changed_since(version)
+    }
+    
+    /// 丢弃版本号不大于 `version` 的变更记录
+    pub fn forget_changes_before(&mut self, version: u64) {
+        self.changes.forget_before(version)
+    }
+    
+    /// 消耗自身，按 field 顺序得到 `(K, E)`
+    pub fn into_vec(self) -> Vec<(K, E)> {
+        self.into_iter()
+            .map(|p| (p.0, p.1))
+            .collect()
     }
     
     pub fn into_raw_parts(self) -> (IdMap<K,V>, FieldCollex<Pair<K,E>,V>) {
@@ -196,9 +1398,270 @@ where
     
     pub fn from_raw_parts(id_map: IdMap<K,V>, collex: FieldCollex<Pair<K,E>,V>) -> Self {
         Self {
-            id_map, collex
+            id_map, collex,
+            changes: ChangeLog::default(),
+            next_fit: None,
+            indexed: true,
+            lazy_index: false,
+            shrink_policy: ShrinkPolicy::default(),
         }
     }
 }
 
-
+#[cfg(test)]
+mod tests {
+    use field_collex::collex::InsertFieldCollexError;
+    use span_core::Span;
+    use crate::{ChangeKind, DefaultId, ModifyManyError, ReplaceError, SwapError};
+    use crate::test_util::{TestO, new_map};
+    
+    #[test]
+    fn test_insert_with_id() {
+        let mut map = new_map();
+        let a = map.insert(TestO(10)).unwrap();
+        map.insert(TestO(20)).unwrap();
+        
+        // 覆盖已有 Id：返回旧元素，新元素按新的 field 值就位
+        assert_eq!(map.insert_with_id(a, TestO(30)).unwrap(), Some(TestO(10)));
+        assert_eq!(map.get_with_id(a), Some(&TestO(30)));
+        assert_eq!(map.id_at(10), None);
+        assert_eq!(map.id_at(30), Some(a));
+        
+        // 新元素被拒绝时旧元素原样放回
+        assert!(matches!(map.insert_with_id(a, TestO(20)), Err(InsertFieldCollexError::AlreadyExist(TestO(20)))));
+        assert!(matches!(map.insert_with_id(a, TestO(200)), Err(InsertFieldCollexError::OutOfSpan(TestO(200)))));
+        assert_eq!(map.get_with_id(a), Some(&TestO(30)));
+        assert_eq!(map.id_at(30), Some(a));
+        
+        // 大于 max_id 的 Id 推进 max_id，之后自动分配的 Id 从它之后开始
+        assert_eq!(map.insert_with_id(DefaultId(50), TestO(40)).unwrap(), None);
+        assert_eq!(map.insert(TestO(50)).unwrap(), DefaultId(51));
+        // 失败的插入不推进 max_id
+        assert!(map.insert_with_id(DefaultId(80), TestO(200)).is_err());
+        assert_eq!(map.insert(TestO(60)).unwrap(), DefaultId(52));
+        assert!(map.validate().is_ok());
+    }
+    
+    #[test]
+    fn test_replace() {
+        let mut map = new_map();
+        let a = map.insert(TestO(10)).unwrap();
+        map.insert(TestO(20)).unwrap();
+        
+        assert_eq!(map.replace(a, TestO(30)).unwrap(), TestO(10));
+        assert_eq!(map.id_at(30), Some(a));
+        assert_eq!(map.id_at(10), None);
+        
+        assert!(matches!(map.replace(a, TestO(20)), Err(ReplaceError::AlreadyExist(TestO(20)))));
+        assert!(matches!(map.replace(a, TestO(200)), Err(ReplaceError::OutOfSpan(TestO(200)))));
+        assert!(matches!(map.replace(DefaultId(99), TestO(50)), Err(ReplaceError::CannotFind(TestO(50)))));
+        assert_eq!(map.get_with_id(a), Some(&TestO(30)));
+        assert_eq!(map.insert(TestO(50)).unwrap(), DefaultId(3));
+        assert!(map.validate().is_ok());
+    }
+    
+    #[test]
+    fn test_swap() {
+        let mut map = new_map();
+        let a = map.insert(TestO(10)).unwrap();
+        let b = map.insert(TestO(20)).unwrap();
+        
+        map.swap(a, b).unwrap();
+        assert_eq!(map.get_with_id(a), Some(&TestO(20)));
+        assert_eq!(map.get_with_id(b), Some(&TestO(10)));
+        assert_eq!(map.ids().collect::<Vec<_>>(), vec![b, a]);
+        
+        assert!(map.swap(a, a).is_ok());
+        assert!(matches!(map.swap(a, DefaultId(99)), Err(SwapError::CannotFind(DefaultId(99)))));
+        assert!(matches!(map.swap(DefaultId(99), a), Err(SwapError::CannotFind(DefaultId(99)))));
+        assert_eq!(map.get_with_id(a), Some(&TestO(20)));
+        assert_eq!(map.get_with_id(b), Some(&TestO(10)));
+        assert!(map.validate().is_ok());
+    }
+    
+    #[test]
+    fn test_for_each_mut() {
+        let mut map = new_map();
+        let a = map.insert(TestO(10)).unwrap();
+        let b = map.insert(TestO(20)).unwrap();
+        let c = map.insert(TestO(30)).unwrap();
+        let version = map.version();
+        
+        // 只读遍历不产生任何变更
+        assert!(map.for_each_mut(|_, _| {}).is_empty());
+        assert_eq!(map.version(), version);
+        
+        // a 挪到空位，b 改到 c 的位置被退回原值
+        let rejected = map.for_each_mut(|id, e| {
+            if id == a {
+                e.0 = 50;
+            } else if id == b {
+                e.0 = 30;
+            }
+        });
+        assert_eq!(rejected, vec![(b, ModifyManyError::AlreadyExist)]);
+        assert_eq!(map.get_with_id(a), Some(&TestO(50)));
+        assert_eq!(map.get_with_id(b), Some(&TestO(20)));
+        assert_eq!(map.id_map[a], 50);
+        assert_eq!(map.ids().collect::<Vec<_>>(), vec![b, c, a]);
+        assert_eq!(map.changed_since(version).collect::<Vec<_>>(), vec![(a, ChangeKind::Modified)]);
+        assert!(map.validate().is_ok());
+    }
+    
+    #[test]
+    fn test_remove_many() {
+        let mut map = new_map();
+        let a = map.insert(TestO(10)).unwrap();
+        let b = map.insert(TestO(20)).unwrap();
+        let c = map.insert(TestO(30)).unwrap();
+        let version = map.version();
+        
+        let removed = map.remove_many([c, a, c, DefaultId(99)]);
+        assert_eq!(removed, vec![
+            (c, Some(TestO(30))),
+            (a, Some(TestO(10))),
+            (c, None),
+            (DefaultId(99), None),
+        ]);
+        assert_eq!(map.ids().collect::<Vec<_>>(), vec![b]);
+        assert!(!map.contains_id(a) && !map.contains_id(c));
+        assert_eq!(map.changed_since(version).count(), 2);
+        assert!(map.validate().is_ok());
+    }
+    
+    #[test]
+    fn test_retain() {
+        let mut map = new_map();
+        let a = map.insert(TestO(10)).unwrap();
+        let b = map.insert(TestO(20)).unwrap();
+        let c = map.insert(TestO(30)).unwrap();
+        let version = map.version();
+        
+        let mut seen = Vec::new();
+        map.retain(|id, e| {
+            seen.push(id);
+            e.0 != 20
+        });
+        assert_eq!(seen, vec![a, b, c]);
+        assert_eq!(map.ids().collect::<Vec<_>>(), vec![a, c]);
+        assert!(!map.contains_id(b));
+        assert_eq!(map.get(30).map(|p| p.0), Some(c));
+        assert_eq!(map.changed_since(version).collect::<Vec<_>>(), vec![(b, ChangeKind::Removed)]);
+        assert!(map.validate().is_ok());
+        
+        map.retain(|_, _| false);
+        assert!(map.is_empty());
+        assert_eq!(map.insert(TestO(20)).unwrap(), DefaultId(4));
+    }
+    
+    #[test]
+    fn test_ids_in_range() {
+        let mut map = new_map();
+        let ids: Vec<_> = [10, 25, 30, 45, 60].into_iter().map(|v| map.insert(TestO(v)).unwrap()).collect();
+        
+        let found: Vec<_> = map.ids_in_range(Span::new_finite(20u32, 50u32)).map(|(id, _)| id).collect();
+        assert_eq!(found, ids[1..4].to_vec());
+        let found: Vec<_> = map.ids_in_range(Span::new_finite(30u32, 31u32)).map(|(id, _)| id).collect();
+        assert_eq!(found, vec![ids[2]]);
+        assert_eq!(map.ids_in_range(Span::new_finite(61u32, 100u32)).count(), 0);
+        assert_eq!(map.ids_in_range(Span::new_finite(0u32, 10u32)).count(), 0);
+    }
+    
+    #[test]
+    fn test_nearest() {
+        let mut map = new_map();
+        assert_eq!(map.nearest(50), None);
+        let a = map.insert(TestO(20)).unwrap();
+        let b = map.insert(TestO(40)).unwrap();
+        
+        assert_eq!(map.nearest_above(20).map(|(id, _)| id), Some(a));
+        assert_eq!(map.nearest_above(21).map(|(id, _)| id), Some(b));
+        assert_eq!(map.nearest_above(41), None);
+        assert_eq!(map.nearest_below(39).map(|(id, _)| id), Some(a));
+        assert_eq!(map.nearest_below(19), None);
+        
+        assert_eq!(map.nearest(29).map(|(id, _)| id), Some(a));
+        assert_eq!(map.nearest(30).map(|(id, _)| id), Some(a));
+        assert_eq!(map.nearest(31).map(|(id, _)| id), Some(b));
+        assert_eq!(map.nearest(90).map(|(id, _)| id), Some(b));
+    }
+    
+    #[test]
+    fn test_split_by() {
+        let mut map = new_map();
+        let ids: Vec<_> = [10, 20, 30, 40].into_iter().map(|v| map.insert(TestO(v)).unwrap()).collect();
+        map.remove(ids[3]);
+        
+        let (low, high) = map.split_by(|_, e| e.0 < 25);
+        assert_eq!(low.ids().collect::<Vec<_>>(), ids[..2].to_vec());
+        assert_eq!(high.ids().collect::<Vec<_>>(), vec![ids[2]]);
+        assert_eq!(high.get_with_id(ids[2]), Some(&TestO(30)));
+        assert!(low.validate().is_ok() && high.validate().is_ok());
+        
+        // 两边都从原 max_id 之后分配，不复用被删除的 Id，但彼此之间不做协调
+        let (mut low, mut high) = (low, high);
+        assert_eq!(low.insert(TestO(50)).unwrap(), DefaultId(5));
+        assert_eq!(high.insert(TestO(60)).unwrap(), DefaultId(5));
+        
+        // 共用一个 IdGen 时新 Id 互不重复
+        let id_gen = low.id_gen();
+        let (x, y) = (id_gen.next(), id_gen.next());
+        low.insert_with_id(x, TestO(70)).unwrap();
+        high.insert_with_id(y, TestO(70)).unwrap();
+        assert_ne!(x, y);
+    }
+    
+    #[test]
+    fn test_merge() {
+        let mut map = new_map();
+        let a = map.insert(TestO(10)).unwrap();
+        let b = map.insert(TestO(20)).unwrap();
+        map.remove(b);
+        
+        let mut other = new_map();
+        for v in [30, 40, 50, 60] {
+            other.insert(TestO(v)).unwrap();
+        }
+        other.remove(DefaultId(1));
+        other.insert(TestO(10)).unwrap();
+        
+        // 已删除的 b 不被复用，与 a 的 field 值冲突的元素被退回
+        let report = map.merge(other);
+        assert_eq!(report.remapped, vec![(b, DefaultId(6))]);
+        assert_eq!(report.rejected, vec![(DefaultId(5), TestO(10))]);
+        assert_eq!(map.ids().collect::<Vec<_>>(), vec![a, DefaultId(6), DefaultId(3), DefaultId(4)]);
+        assert!(!map.contains_id(b) && !map.contains_id(DefaultId(5)));
+        assert_eq!(map.insert(TestO(70)).unwrap(), DefaultId(7));
+        assert!(map.validate().is_ok());
+    }
+    
+    #[test]
+    fn test_extend_skips_rejected() {
+        let mut map = new_map();
+        map.insert(TestO(10)).unwrap();
+        map.extend([TestO(20), TestO(200), TestO(10), TestO(30)]);
+        
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.id_map().len(), 3);
+        assert_eq!(map.values().map(|e| e.0).collect::<Vec<_>>(), vec![10, 20, 30]);
+        assert!(map.validate().is_ok());
+    }
+    
+    #[test]
+    fn test_peek_next_id() {
+        let mut map = new_map();
+        assert_eq!(map.max_id(), DefaultId(0));
+        assert_eq!(map.peek_next_id(), Some(DefaultId(1)));
+        
+        let next = map.peek_next_id().unwrap();
+        assert_eq!(map.insert(TestO(10)).unwrap(), next);
+        assert_eq!(map.max_id(), next);
+        
+        map.remove(next);
+        assert_eq!(map.max_id(), next);
+        assert_eq!(map.peek_next_id(), Some(DefaultId(2)));
+        
+        map.id_map.set_max_id(DefaultId(u64::MAX));
+        assert_eq!(map.peek_next_id(), None);
+    }
+}
@@ -0,0 +1,107 @@
+//! 一次声明 OrdIdMap 类型及其 Id 类型的宏
+
+/// 同时生成 Id 类型与对应的 OrdIdMap 类型
+///
+/// `type` 形式生成类型别名；`struct` 形式生成包装 OrdIdMap 的新类型，可通过 Deref 使用全部方法，
+/// 也便于在其上添加自己的方法。Id 名称后加 `: Ord` 时 Id 类型额外派生全序
+///
+/// ```ignore
+/// new_allocator_type! {
+///     pub type SectionMap<SectionId> = Section => u32;
+///     #[derive(Debug)]
+///     pub struct NodeArena<NodeId: Ord> = Node => u64;
+/// }
+/// ```
+#[macro_export]
+macro_rules! new_allocator_type {
+    () => {};
+
+    // 类型别名：OrdIdMap<Id, 元素, field 值>
+    (
+        $(#[$meta:meta])*
+        $vis:vis type $name:ident<$id:ident $(: $ord:ident)?> = $elem:ty => $field:ty;
+        $($rest:tt)*
+    ) => {
+        $crate::new_id_type! {
+            $vis struct $id $(: $ord)?;
+        }
+
+        $(#[$meta])*
+        $vis type $name = $crate::OrdIdMap<$id, $elem, $field>;
+
+        $crate::new_allocator_type!($($rest)*);
+    };
+
+    // 新类型：包装 OrdIdMap，Deref 到内部的 OrdIdMap
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident<$id:ident $(: $ord:ident)?> = $elem:ty => $field:ty;
+        $($rest:tt)*
+    ) => {
+        $crate::new_id_type! {
+            $vis struct $id $(: $ord)?;
+        }
+
+        $(#[$meta])*
+        $vis struct $name(pub $crate::OrdIdMap<$id, $elem, $field>);
+
+        impl $name {
+            pub fn new(
+                span: $crate::span_core::Span<$field>,
+                unit: $field,
+            ) -> Result<Self, $crate::field_collex::collex::NewFieldCollexError<$field>> {
+                $crate::OrdIdMap::new(span, unit).map(Self)
+            }
+
+            pub fn into_inner(self) -> $crate::OrdIdMap<$id, $elem, $field> {
+                self.0
+            }
+        }
+
+        impl From<$crate::OrdIdMap<$id, $elem, $field>> for $name {
+            fn from(map: $crate::OrdIdMap<$id, $elem, $field>) -> Self {
+                Self(map)
+            }
+        }
+
+        impl ::core::ops::Deref for $name {
+            type Target = $crate::OrdIdMap<$id, $elem, $field>;
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl ::core::ops::DerefMut for $name {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.0
+            }
+        }
+
+        $crate::new_allocator_type!($($rest)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use span_core::Span;
+    use crate::test_util::TestO;
+    
+    new_allocator_type! {
+        type TestMap<TestId> = TestO => u32;
+        #[derive(Debug)]
+        struct TestArena<ArenaId: Ord> = TestO => u32;
+    }
+    
+    #[test]
+    fn test_new_allocator_type() {
+        let mut map = TestMap::new(Span::new_finite(0u32, 100u32), 10).unwrap();
+        assert_eq!(map.insert(TestO(10)).unwrap(), TestId(1));
+        
+        let mut arena = TestArena::new(Span::new_finite(0u32, 100u32), 10).unwrap();
+        let a = arena.insert(TestO(20)).unwrap();
+        let b = arena.insert(TestO(30)).unwrap();
+        assert!(a < b);
+        assert_eq!(arena.get_with_id(b), Some(&TestO(30)));
+        assert_eq!(arena.into_inner().len(), 2);
+    }
+}
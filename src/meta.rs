@@ -0,0 +1,91 @@
+//! 元素元数据：创建时间、创建与最后修改的版本号、用户标签
+//!
+//! 默认关闭；开启后由变更记录自动维护，插入、修改、删除时同步更新
+
+use std::time::SystemTime;
+use field_collex::{Collexetable, FieldValue};
+use crate::{Id, OrdIdMap};
+
+/// 单个 Id 的元数据
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PairMeta {
+    /// 插入时间（对开启前已存在的元素为开启时间）
+    pub created_at: SystemTime,
+    /// 插入时的版本号
+    pub created_version: u64,
+    /// 最后一次修改的版本号，未修改过时等于 `created_version`
+    pub modified_version: u64,
+    /// 用户自定义标签
+    pub tag: Option<String>,
+}
+
+impl PairMeta {
+    pub(crate) fn new(version: u64) -> Self {
+        Self {
+            created_at: SystemTime::now(),
+            created_version: version,
+            modified_version: version,
+            tag: None,
+        }
+    }
+}
+
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    /// 开启元数据记录，已存在的元素以当前时间与版本号补齐
+    pub fn enable_meta(&mut self) {
+        let ids: Vec<K> = self.ids().collect();
+        self.changes.enable_meta(ids);
+    }
+    
+    /// 关闭元数据记录并丢弃已有的元数据
+    pub fn disable_meta(&mut self) {
+        self.changes.disable_meta();
+    }
+    
+    /// Id 对应的元数据，未开启或 Id 不存在时为 None
+    pub fn meta(&self, id: K) -> Option<&PairMeta> {
+        self.changes.meta(id)
+    }
+    
+    /// 设置 Id 的用户标签，返回旧标签；未开启或 Id 不存在时返回 Err 并带回新标签
+    pub fn set_tag(&mut self, id: K, tag: Option<String>) -> Result<Option<String>, Option<String>> {
+        match self.changes.meta_mut(id) {
+            Some(meta) => Ok(std::mem::replace(&mut meta.tag, tag)),
+            None => Err(tag),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::{TestO, new_map};
+    
+    #[test]
+    fn test_meta() {
+        let mut map = new_map();
+        let a = map.insert(TestO(10)).unwrap();
+        assert!(map.meta(a).is_none());
+        
+        map.enable_meta();
+        let created = map.meta(a).unwrap().created_version;
+        let b = map.insert(TestO(20)).unwrap();
+        assert_eq!(map.meta(b).unwrap().created_version, map.version());
+        
+        map.modify(a, |e| e.0 = 30).unwrap();
+        let meta = map.meta(a).unwrap();
+        assert_eq!(meta.created_version, created);
+        assert_eq!(meta.modified_version, map.version());
+        
+        assert_eq!(map.set_tag(b, Some("audit".into())), Ok(None));
+        assert_eq!(map.meta(b).unwrap().tag.as_deref(), Some("audit"));
+        
+        map.remove(b);
+        assert!(map.meta(b).is_none());
+        assert!(map.set_tag(b, None).is_err());
+    }
+}
@@ -1,14 +1,64 @@
+use std::cmp::Ordering;
 use std::ops::{Deref, DerefMut};
 use field_collex::{Collexetable};
-use crate::Id;
+use crate::{Id, ModifyGuard};
 
+/// 默认序列化为紧凑的 `[id, value]`；开启 `named-serde` feature 后序列化为 `{"id": .., "value": ..}`
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(not(feature = "named-serde"), derive(serde::Serialize, serde::Deserialize))]
 pub struct Pair<K,O>(pub K, pub O)
 where
     K: Id,
 ;
 
+impl<K: Id,E> Pair<K,E> {
+    pub fn id(&self) -> K {
+        self.0
+    }
+    
+    pub fn value(&self) -> &E {
+        &self.1
+    }
+    
+    pub fn value_mut(&mut self) -> &mut E {
+        &mut self.1
+    }
+    
+    /// 拆分为 `(Id, 元素)`
+    pub fn into_parts(self) -> (K, E) {
+        (self.0, self.1)
+    }
+    
+    /// 转换元素类型，Id 保持不变
+    pub fn map<F, E2>(self, f: F) -> Pair<K, E2>
+    where
+        F: FnOnce(E) -> E2,
+    {
+        Pair(self.0, f(self.1))
+    }
+    
+    /// 可能失败的元素类型转换，Id 保持不变
+    pub fn try_map<F, E2, Err>(self, f: F) -> Result<Pair<K, E2>, Err>
+    where
+        F: FnOnce(E) -> Result<E2, Err>,
+    {
+        Ok(Pair(self.0, f(self.1)?))
+    }
+    
+    /// 只按 Id 比较
+    pub fn cmp_by_id(&self, other: &Self) -> Ordering {
+        self.0.as_u64().cmp(&other.0.as_u64())
+    }
+    
+    /// 只按元素比较，不考虑 Id
+    pub fn cmp_by_value(&self, other: &Self) -> Ordering
+    where
+        E: Ord,
+    {
+        self.1.cmp(&other.1)
+    }
+}
+
 impl<K: Id,E> Deref for Pair<K,E>{
     type Target = E;
     fn deref(&self) -> &Self::Target {
@@ -39,3 +89,156 @@ where
         self.1.collexate_mut()
     }
 }
+
+#[cfg(feature = "named-serde")]
+mod named {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use serde::ser::SerializeStruct;
+    use crate::Id;
+    use super::Pair;
+    
+    #[derive(Deserialize)]
+    #[serde(rename = "Pair")]
+    struct NamedPair<K, E> {
+        id: K,
+        value: E,
+    }
+    
+    impl<K: Id + Serialize, E: Serialize> Serialize for Pair<K, E> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut state = serializer.serialize_struct("Pair", 2)?;
+            state.serialize_field("id", &self.0)?;
+            state.serialize_field("value", &self.1)?;
+            state.end()
+        }
+    }
+    
+    impl<'de, K: Id + Deserialize<'de>, E: Deserialize<'de>> Deserialize<'de> for Pair<K, E> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let NamedPair { id, value } = NamedPair::deserialize(deserializer)?;
+            Ok(Pair(id, value))
+        }
+    }
+}
+
+/// 按 Id 排序的 Pair 包装（派生的 Ord 是先 Id 后元素，此包装明确只看 Id）
+#[derive(Debug, Clone, Copy)]
+pub struct ById<P>(pub P);
+
+/// 只按元素排序的 Pair 包装，忽略 Id
+#[derive(Debug, Clone, Copy)]
+pub struct ByValue<P>(pub P);
+
+impl<K: Id, E> PartialEq for ById<Pair<K, E>> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.0 == other.0.0
+    }
+}
+
+impl<K: Id, E> Eq for ById<Pair<K, E>> {}
+
+impl<K: Id, E> PartialOrd for ById<Pair<K, E>> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Id, E> Ord for ById<Pair<K, E>> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp_by_id(&other.0)
+    }
+}
+
+impl<K: Id, E: Ord> PartialEq for ByValue<Pair<K, E>> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.1 == other.0.1
+    }
+}
+
+impl<K: Id, E: Ord> Eq for ByValue<Pair<K, E>> {}
+
+impl<K: Id, E: Ord> PartialOrd for ByValue<Pair<K, E>> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Id, E: Ord> Ord for ByValue<Pair<K, E>> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp_by_value(&other.0)
+    }
+}
+
+/// 借用形式的 Pair：带上 Id 的元素引用
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct PairRef<'a, K, E>(pub K, pub &'a E)
+where
+    K: Id,
+;
+
+impl<'a, K: Id, E> PairRef<'a, K, E> {
+    pub fn id(&self) -> K {
+        self.0
+    }
+    
+    pub fn value(&self) -> &'a E {
+        self.1
+    }
+    
+    /// 克隆元素，得到拥有所有权的 Pair
+    pub fn cloned(&self) -> Pair<K, E>
+    where
+        E: Clone,
+    {
+        Pair(self.0, self.1.clone())
+    }
+}
+
+impl<K: Id, E> Deref for PairRef<'_, K, E> {
+    type Target = E;
+    fn deref(&self) -> &Self::Target {
+        self.1
+    }
+}
+
+impl<'a, K: Id, E> From<&'a Pair<K, E>> for PairRef<'a, K, E> {
+    fn from(pair: &'a Pair<K, E>) -> Self {
+        Self(pair.0, &pair.1)
+    }
+}
+
+/// 可变形式的 Pair：即元素的可变访问守卫，释放时写回 collex 与 id_map
+pub type PairMut<'a, K, E, V> = ModifyGuard<'a, K, E, V>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefaultId;
+    
+    #[test]
+    fn test_pair_ordering() {
+        let mut pairs = [
+            Pair(DefaultId(2), 10u32),
+            Pair(DefaultId(1), 30),
+            Pair(DefaultId(3), 20),
+        ];
+        
+        pairs.sort_by(Pair::cmp_by_value);
+        assert_eq!(pairs.iter().map(|p| p.id()).collect::<Vec<_>>(), vec![DefaultId(2), DefaultId(3), DefaultId(1)]);
+        
+        pairs.sort_by_key(|p| ById(*p));
+        assert_eq!(pairs.iter().map(|p| p.id()).collect::<Vec<_>>(), vec![DefaultId(1), DefaultId(2), DefaultId(3)]);
+        
+        let max = pairs.iter().copied().map(ByValue).max().unwrap();
+        assert_eq!(max.0, Pair(DefaultId(1), 30));
+    }
+    
+    #[cfg(feature = "named-serde")]
+    #[test]
+    fn test_named_serde() {
+        let pair = Pair(DefaultId(1), 30u32);
+        let json = serde_json::to_string(&pair).unwrap();
+        assert_eq!(json, r#"{"id":1,"value":30}"#);
+        assert_eq!(serde_json::from_str::<Pair<DefaultId, u32>>(&json).unwrap(), pair);
+    }
+}
@@ -0,0 +1,252 @@
+//! rayon 并行遍历与批量插入：`par_iter` 产出 `(K, &E)`；OrdIdMap 的可变遍历经 `ParIterMut` 守卫，释放时写回
+
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, IntoParallelRefMutIterator, Map, ParallelIterator};
+use field_collex::{Collexetable, FieldValue};
+use field_collex::collex::InsertFieldCollexError;
+use crate::guard::write_back;
+use crate::{ChangeKind, Id, IdMap, ImportError, ImportRejection, ModifyManyError, OrdIdMap, Pair};
+
+type RefFn<'a, K, E> = fn(&'a Pair<K, E>) -> (K, &'a E);
+type MutFn<'a, K, E, V> = fn(&'a mut (V, Pair<K, E>)) -> (K, &'a mut E);
+type IdRefFn<'a, K, V> = fn((&'a u64, &'a V)) -> (K, &'a V);
+type IdMutFn<'a, K, V> = fn((&'a u64, &'a mut V)) -> (K, &'a mut V);
+
+/// 按 field 顺序收集引用后并行产出 `(K, &E)`
+impl<'a, K, E, V> IntoParallelIterator for &'a OrdIdMap<K, E, V>
+where
+    K: Id + Send + Sync,
+    E: Collexetable<V> + Sync,
+    V: FieldValue,
+{
+    type Iter = Map<rayon::vec::IntoIter<&'a Pair<K, E>>, RefFn<'a, K, E>>;
+    type Item = (K, &'a E);
+    
+    fn into_par_iter(self) -> Self::Iter {
+        let pairs: Vec<&'a Pair<K, E>> = self.collex.iter().collect();
+        let f: RefFn<'a, K, E> = |p| (p.0, &p.1);
+        pairs.into_par_iter().map(f)
+    }
+}
+
+impl<'a, K, V> IntoParallelIterator for &'a IdMap<K, V>
+where
+    K: Id + Send,
+    V: Sync,
+{
+    type Iter = Map<rayon::collections::hash_map::Iter<'a, u64, V>, IdRefFn<'a, K, V>>;
+    type Item = (K, &'a V);
+    
+    fn into_par_iter(self) -> Self::Iter {
+        let f: IdRefFn<'a, K, V> = |(id, v)| (K::from_u64(*id), v);
+        self.inner.par_iter().map(f)
+    }
+}
+
+impl<'a, K, V> IntoParallelIterator for &'a mut IdMap<K, V>
+where
+    K: Id + Send,
+    V: Send,
+{
+    type Iter = Map<rayon::collections::hash_map::IterMut<'a, u64, V>, IdMutFn<'a, K, V>>;
+    type Item = (K, &'a mut V);
+    
+    fn into_par_iter(self) -> Self::Iter {
+        let f: IdMutFn<'a, K, V> = |(id, v)| (K::from_u64(*id), v);
+        self.inner.par_iter_mut().map(f)
+    }
+}
+
+/// 并行可变遍历的守卫
+///
+/// 创建时所有元素被移出 collex；对 `&mut ParIterMut` 并行遍历得到 `(K, &mut E)`，
+/// 释放（或调用 `finish`）时统一重新 collexate 并写回 collex 与 id_map，规则同 `modify_many`
+pub struct ParIterMut<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    map: &'a mut OrdIdMap<K, E, V>,
+    pending: Vec<(V, Pair<K, E>)>,
+}
+
+impl<'a, K, E, V> ParIterMut<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    fn new(map: &'a mut OrdIdMap<K, E, V>) -> Self {
+        let values: Vec<V> = map.collex.iter().map(|p| p.collexate()).collect();
+        let pending = values
+            .into_iter()
+            .filter_map(|v| map.collex.remove(v).ok().map(|pair| (v, pair)))
+            .collect();
+        Self { map, pending }
+    }
+    
+    /// 立即写回，返回因越界或冲突而退回原值的 Id 及原因
+    pub fn finish(mut self) -> Vec<(K, ModifyManyError)> {
+        let pending = std::mem::take(&mut self.pending);
+        write_back(self.map, pending)
+    }
+}
+
+impl<'a, 'b, K, E, V> IntoParallelIterator for &'b mut ParIterMut<'a, K, E, V>
+where
+    K: Id + Send,
+    E: Collexetable<V> + Send,
+    V: FieldValue + Send,
+{
+    type Iter = Map<rayon::slice::IterMut<'b, (V, Pair<K, E>)>, MutFn<'b, K, E, V>>;
+    type Item = (K, &'b mut E);
+    
+    fn into_par_iter(self) -> Self::Iter {
+        let f: MutFn<'b, K, E, V> = |(_, p)| (p.0, &mut p.1);
+        self.pending.par_iter_mut().map(f)
+    }
+}
+
+impl<'a, K, E, V> Drop for ParIterMut<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    fn drop(&mut self) {
+        let pending = std::mem::take(&mut self.pending);
+        write_back(self.map, pending);
+    }
+}
+
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    /// 取出所有元素供并行修改，见 `ParIterMut`
+    pub fn par_iter_mut(&mut self) -> ParIterMut<'_, K, E, V> {
+        ParIterMut::new(self)
+    }
+    
+    /// 并行修改所有元素，结束后写回，返回退回原值的 Id 及原因
+    pub fn par_for_each_mut<F>(&mut self, f: F) -> Vec<(K, ModifyManyError)>
+    where
+        K: Send,
+        E: Send,
+        V: Send,
+        F: Fn(K, &mut E) + Sync + Send,
+    {
+        let mut guard = self.par_iter_mut();
+        (&mut guard).into_par_iter().for_each(|(id, e)| f(id, e));
+        guard.finish()
+    }
+    
+    /// 并行批量插入：先一次性预留 Id 区间，再并行 collexate 并检查 span，最后按顺序逐个提交
+    ///
+    /// 第 `i` 个元素的 Id 总是调用前的 max_id + 1 + i，与线程调度无关；被拒绝的元素的 Id 不会回收。
+    /// 被拒绝的元素连同原因按传入顺序返回，其余元素均已插入
+    pub fn par_extend(&mut self, items: Vec<E>) -> Result<Vec<K>, ImportError<K, E>>
+    where
+        K: Send,
+        E: Send,
+        V: Send + Sync,
+    {
+        if items.is_empty() {
+            return Ok(Vec::new());
+        }
+        let first = self.id_map.max_id().as_u64() + 1;
+        self.id_map.bump_max_id(K::from_u64(first + items.len() as u64 - 1));
+        let bounds = self.finite_bounds();
+        
+        let checked: Vec<(K, V, bool, E)> = items
+            .into_par_iter()
+            .enumerate()
+            .map(|(i, elem)| {
+                let v = elem.collexate();
+                let in_span = bounds.is_none_or(|(start, end)| start <= v && v < end);
+                (K::from_u64(first + i as u64), v, in_span, elem)
+            })
+            .collect();
+        
+        self.reserve(checked.len());
+        let mut ids = Vec::with_capacity(checked.len());
+        let mut rejected = Vec::new();
+        for (id, v, in_span, elem) in checked {
+            if !in_span {
+                rejected.push((id, ImportRejection::OutOfSpan(elem)));
+                continue;
+            }
+            match self.collex.insert(Pair(id, elem)) {
+                Ok(_) => {
+                    self.set_index(id, v);
+                    self.changes.record(id, ChangeKind::Inserted);
+                    ids.push(id);
+                }
+                Err(InsertFieldCollexError::OutOfSpan(p)) => rejected.push((id, ImportRejection::OutOfSpan(p.1))),
+                Err(InsertFieldCollexError::AlreadyExist(p)) => rejected.push((id, ImportRejection::AlreadyExist(p.1))),
+            }
+        }
+        
+        if rejected.is_empty() {
+            Ok(ids)
+        } else {
+            Err(ImportError { rejected })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rayon::prelude::*;
+    use span_core::Span;
+    use crate::{DefaultId, ImportRejection, ModifyManyError, OrdIdMap};
+    use crate::test_util::TestO;
+    
+    #[test]
+    fn test_par_iter() {
+        let mut map: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::from_iter_with(
+            Span::new_finite(0u32, 1000u32),
+            10,
+            (0..50u32).map(|i| TestO(i * 10)),
+        ).unwrap();
+        
+        let sum: u32 = map.par_iter().map(|(_, e)| e.0).sum();
+        assert_eq!(sum, (0..50u32).map(|i| i * 10).sum::<u32>());
+        let ids: u64 = map.id_map.par_iter().map(|(id, _)| id.0).sum();
+        assert_eq!(ids, (1..=50u64).sum::<u64>());
+        
+        // 整体平移，最后一个元素越界后退回原值
+        let rejected = map.par_for_each_mut(|_, e| e.0 += 510);
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].1, ModifyManyError::OutOfSpan);
+        assert!(map.validate().is_ok());
+        assert_eq!(map.len(), 50);
+        
+        let mut guard = map.par_iter_mut();
+        (&mut guard).into_par_iter().for_each(|(_, e)| e.0 += 1);
+        drop(guard);
+        assert!(map.values().all(|e| e.0 % 10 == 1));
+        assert!(map.validate().is_ok());
+    }
+    
+    #[test]
+    fn test_par_extend_deterministic_ids() {
+        let mut map: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::new(Span::new_finite(0u32, 1000u32), 10).unwrap();
+        map.insert(TestO(0)).unwrap();
+        
+        let ids = map.par_extend((1..50u32).map(|i| TestO(i * 10)).collect()).unwrap();
+        assert_eq!(ids, (2..=50u64).map(DefaultId).collect::<Vec<_>>());
+        assert_eq!(map.get_with_id(DefaultId(50)), Some(&TestO(490)));
+        
+        let err = map.par_extend(vec![TestO(500), TestO(2000), TestO(10)]).unwrap_err();
+        assert_eq!(err.rejected, vec![
+            (DefaultId(52), ImportRejection::OutOfSpan(TestO(2000))),
+            (DefaultId(53), ImportRejection::AlreadyExist(TestO(10))),
+        ]);
+        assert_eq!(map.get_with_id(DefaultId(51)), Some(&TestO(500)));
+        assert_eq!(map.insert(TestO(510)).unwrap(), DefaultId(54));
+        assert!(map.validate().is_ok());
+    }
+}
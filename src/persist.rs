@@ -0,0 +1,247 @@
+//! 文件读写：以完整格式（含 max_id）保存为 JSON，写入先落到临时文件再原子地重命名
+//!
+//! `*_checked` 系列在内容末尾追加 4 字节小端 CRC32，加载时校验，部分写入等损坏会报 `CorruptSnapshot`
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use field_collex::{Collexetable, FieldValue};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use crate::{Id, OrdIdMap, PersistError};
+use crate::full::{as_full, FullOrdIdMap};
+
+/// 与目标文件同目录的临时文件，保证 rename 不跨文件系统
+///
+/// 文件名带有进程号与进程内递增的序号，同时保存到同一路径的多个写入者不会共用临时文件
+pub(crate) fn temp_path(path: &Path) -> PathBuf {
+    static SEQ: AtomicU64 = AtomicU64::new(0);
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.{}.tmp", process::id(), SEQ.fetch_add(1, Ordering::Relaxed)));
+    path.with_file_name(name)
+}
+
+/// 目标文件所在的目录，相对路径只有文件名时为当前目录
+pub(crate) fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+/// rename 之后对所在目录落盘，使重命名本身在崩溃后依然有效；非 Unix 平台无法打开目录，不做处理
+fn sync_dir(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    File::open(parent_dir(path))?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// 把 `write` 写出的内容原子地落到 `path`：先写临时文件并落盘，再重命名覆盖，最后落盘所在目录
+pub(crate) fn write_atomic<F>(path: &Path, write: F) -> Result<(), PersistError>
+where
+    F: FnOnce(&mut BufWriter<File>) -> Result<(), PersistError>,
+{
+    let tmp = temp_path(path);
+    let result: Result<(), PersistError> = (|| {
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        write(&mut writer)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+        fs::rename(&tmp, path)?;
+        sync_dir(path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    /// 保存到文件；中途失败时原文件保持不变
+    pub fn save_to_path(&self, path: impl AsRef<Path>) -> Result<(), PersistError>
+    where
+        K: Serialize,
+        E: Serialize,
+        V: Serialize,
+    {
+        write_atomic(path.as_ref(), |w| Ok(serde_json::to_writer(w, &as_full(self))?))
+    }
+    
+    /// 从 `save_to_path` 保存的文件加载，max_id 一并恢复
+    pub fn load_from_path(path: impl AsRef<Path>) -> Result<Self, PersistError>
+    where
+        K: DeserializeOwned,
+        E: DeserializeOwned,
+        V: DeserializeOwned + fmt::Debug,
+    {
+        let reader = BufReader::new(File::open(path)?);
+        let full: FullOrdIdMap<K, E, V> = serde_json::from_reader(reader)?;
+        Ok(full.into_inner())
+    }
+    
+    /// 序列化为带 CRC32 校验和的字节
+    pub fn to_checked_bytes(&self) -> Result<Vec<u8>, PersistError>
+    where
+        K: Serialize,
+        E: Serialize,
+        V: Serialize,
+    {
+        let mut bytes = serde_json::to_vec(&as_full(self))?;
+        let crc = crc32fast::hash(&bytes);
+        bytes.extend_from_slice(&crc.to_le_bytes());
+        Ok(bytes)
+    }
+    
+    /// 校验 CRC32 后反序列化 `to_checked_bytes` 的结果
+    pub fn from_checked_bytes(bytes: &[u8]) -> Result<Self, PersistError>
+    where
+        K: DeserializeOwned,
+        E: DeserializeOwned,
+        V: DeserializeOwned + fmt::Debug,
+    {
+        let Some(split) = bytes.len().checked_sub(4) else {
+            return Err(PersistError::CorruptSnapshot { expected: 0, actual: crc32fast::hash(bytes) });
+        };
+        let (payload, trailer) = bytes.split_at(split);
+        let expected = u32::from_le_bytes(trailer.try_into().expect("trailer is 4 bytes"));
+        let actual = crc32fast::hash(payload);
+        if expected != actual {
+            return Err(PersistError::CorruptSnapshot { expected, actual });
+        }
+        let full: FullOrdIdMap<K, E, V> = serde_json::from_slice(payload)?;
+        Ok(full.into_inner())
+    }
+    
+    /// 带校验和保存到文件；中途失败时原文件保持不变
+    pub fn save_checked(&self, path: impl AsRef<Path>) -> Result<(), PersistError>
+    where
+        K: Serialize,
+        E: Serialize,
+        V: Serialize,
+    {
+        let bytes = self.to_checked_bytes()?;
+        write_atomic(path.as_ref(), |w| Ok(w.write_all(&bytes)?))
+    }
+    
+    /// 从 `save_checked` 保存的文件加载，校验和不符时返回 `CorruptSnapshot`
+    pub fn load_checked(path: impl AsRef<Path>) -> Result<Self, PersistError>
+    where
+        K: DeserializeOwned,
+        E: DeserializeOwned,
+        V: DeserializeOwned + fmt::Debug,
+    {
+        let mut bytes = Vec::new();
+        File::open(path)?.read_to_end(&mut bytes)?;
+        Self::from_checked_bytes(&bytes)
+    }
+    
+    /// 以 zstd 压缩保存到文件，`level` 为压缩等级（0 表示 zstd 默认等级）；中途失败时原文件保持不变
+    #[cfg(feature = "compress")]
+    pub fn save_compressed(&self, path: impl AsRef<Path>, level: i32) -> Result<(), PersistError>
+    where
+        K: Serialize,
+        E: Serialize,
+        V: Serialize,
+    {
+        write_atomic(path.as_ref(), |w| {
+            let mut encoder = zstd::Encoder::new(w, level)?;
+            serde_json::to_writer(&mut encoder, &as_full(self))?;
+            encoder.finish()?;
+            Ok(())
+        })
+    }
+    
+    /// 从 `save_compressed` 保存的文件加载，max_id 一并恢复
+    #[cfg(feature = "compress")]
+    pub fn load_compressed(path: impl AsRef<Path>) -> Result<Self, PersistError>
+    where
+        K: DeserializeOwned,
+        E: DeserializeOwned,
+        V: DeserializeOwned + fmt::Debug,
+    {
+        let decoder = zstd::Decoder::new(File::open(path)?)?;
+        let full: FullOrdIdMap<K, E, V> = serde_json::from_reader(decoder)?;
+        Ok(full.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use field_collex::collex::serialize::{default_span, default_unit};
+    use crate::{DefaultId, OrdIdMap, PersistError};
+    use crate::test_util::TestO;
+    use super::{parent_dir, temp_path};
+    
+    #[test]
+    fn test_temp_path_unique() {
+        let path = Path::new("dir/map.json");
+        let (a, b) = (temp_path(path), temp_path(path));
+        assert_ne!(a, b);
+        assert_eq!(a.parent(), Some(Path::new("dir")));
+        assert!(a.file_name().unwrap().to_string_lossy().starts_with("map.json."));
+        assert_eq!(parent_dir(Path::new("map.json")), Path::new("."));
+    }
+    
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("obj-alloc-persist-{}.json", std::process::id()));
+        let mut map: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::new(default_span(), default_unit()).unwrap();
+        map.insert(TestO(10)).unwrap();
+        let last = map.insert(TestO(20)).unwrap();
+        map.remove(last);
+        
+        map.save_to_path(&path).unwrap();
+        let loaded: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::load_from_path(&path).unwrap();
+        assert_eq!(loaded, map);
+        assert_eq!(loaded.id_map.max_id(), last);
+        
+        std::fs::write(&path, "not json").unwrap();
+        let err = OrdIdMap::<DefaultId, TestO, u32>::load_from_path(&path).unwrap_err();
+        assert!(matches!(err, PersistError::Format(_)));
+        std::fs::remove_file(&path).unwrap();
+        
+        let err = OrdIdMap::<DefaultId, TestO, u32>::load_from_path(&path).unwrap_err();
+        assert!(matches!(err, PersistError::Io(_)));
+    }
+    
+    #[test]
+    fn test_checked_bytes() {
+        let mut map: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::new(default_span(), default_unit()).unwrap();
+        map.insert(TestO(10)).unwrap();
+        
+        let mut bytes = map.to_checked_bytes().unwrap();
+        assert_eq!(OrdIdMap::<DefaultId, TestO, u32>::from_checked_bytes(&bytes).unwrap(), map);
+        
+        // 模拟部分写入
+        bytes.truncate(bytes.len() - 6);
+        let err = OrdIdMap::<DefaultId, TestO, u32>::from_checked_bytes(&bytes).unwrap_err();
+        assert!(matches!(err, PersistError::CorruptSnapshot { .. }));
+    }
+    
+    #[cfg(feature = "compress")]
+    #[test]
+    fn test_save_and_load_compressed() {
+        let path = std::env::temp_dir().join(format!("obj-alloc-compressed-{}.json.zst", std::process::id()));
+        let mut map: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::new(default_span(), default_unit()).unwrap();
+        for v in 0..100 {
+            map.insert(TestO(v * 10)).unwrap();
+        }
+        
+        map.save_compressed(&path, 3).unwrap();
+        let loaded: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::load_compressed(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, map);
+    }
+}
@@ -0,0 +1,11 @@
+//! 常用类型一次导入：`use obj_alloc::prelude::*;`
+
+pub use crate::{new_allocator_type, new_id_type};
+pub use crate::{DefaultId, Id, IdMap, OrdIdMap, OrdIdMapBuilder, Pair, ShrinkPolicy};
+pub use crate::{
+    BuildError, IdError, InconsistentState, InsertError, LoadError, ModifyError, ModifyManyError, PersistError,
+    RemoveError, ReplaceError, SwapError,
+};
+pub use field_collex::{Collexetable, FieldValue};
+pub use field_collex::collex::{InsertFieldCollexError, ModifyFieldCollexError, NewFieldCollexError};
+pub use span_core::Span;
@@ -0,0 +1,15 @@
+/// `merge` 的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeReport<K, E> {
+    /// 因 Id 冲突而重新分配的 `(原 Id, 新 Id)`
+    pub remapped: Vec<(K, K)>,
+    /// 因越界或 field 值冲突而未能并入的 `(原 Id, 元素)`
+    pub rejected: Vec<(K, E)>,
+}
+
+/// `compact` 的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactReport<K, V> {
+    /// 被移动的元素的 `(Id, 原 field 值, 新 field 值)`，按 field 顺序排列
+    pub moved: Vec<(K, V, V)>,
+}
@@ -0,0 +1,261 @@
+//! SecondaryMap：为已有 Id 附加辅助数据
+//! 不生成 Id，只按 Id 存取；Id 由 IdMap / OrdIdMap 分配
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::ops::{Index, IndexMut};
+use crate::Id;
+
+// ============================ SecondaryMap 核心实现（稠密存储） ============================
+/// 稠密辅助表：以 Id 的 u64 值为下标存放在 Vec 中，适合大部分 Id 都附有数据的场景
+#[derive(Debug, Clone)]
+pub struct SecondaryMap<K: Id, D> {
+    slots: Vec<Option<D>>, // 底层存储：下标即 Id
+    len: usize,            // 已附加数据的 Id 数量
+    _marker: PhantomData<K>,
+}
+
+impl<K: Id, D> Default for SecondaryMap<K, D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Id, D> SecondaryMap<K, D> {
+    /// 创建空的 SecondaryMap
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+    
+    /// 创建指定初始容量的 SecondaryMap（容量按最大 Id 计）
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slots: Vec::with_capacity(capacity),
+            len: 0,
+            _marker: PhantomData,
+        }
+    }
+    
+    /// 为 Id 附加数据，返回旧数据（若存在）
+    pub fn insert(&mut self, id: K, data: D) -> Option<D> {
+        let idx = id.as_u64() as usize;
+        if idx >= self.slots.len() {
+            self.slots.resize_with(idx + 1, || None);
+        }
+        let old = self.slots[idx].replace(data);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+    
+    /// 根据 Id 查询数据
+    pub fn get(&self, id: K) -> Option<&D> {
+        self.slots.get(id.as_u64() as usize)?.as_ref()
+    }
+    
+    /// 根据 Id 查询可变数据
+    pub fn get_mut(&mut self, id: K) -> Option<&mut D> {
+        self.slots.get_mut(id.as_u64() as usize)?.as_mut()
+    }
+    
+    /// 根据 Id 删除数据
+    pub fn remove(&mut self, id: K) -> Option<D> {
+        let old = self.slots.get_mut(id.as_u64() as usize)?.take();
+        if old.is_some() {
+            self.len -= 1;
+        }
+        old
+    }
+    
+    /// 判断 Id 是否附有数据
+    pub fn contains_id(&self, id: K) -> bool {
+        self.get(id).is_some()
+    }
+    
+    /// 获取元素数量
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    
+    /// 判断是否为空
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    
+    /// 清空所有数据
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.len = 0;
+    }
+    
+    /// 按 Id 升序遍历 `(K, &D)`
+    pub fn iter(&self) -> impl Iterator<Item = (K, &D)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(i, d)| d.as_ref().map(|d| (K::from_u64(i as u64), d)))
+    }
+    
+    /// 按 Id 升序遍历 `(K, &mut D)`
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (K, &mut D)> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, d)| d.as_mut().map(|d| (K::from_u64(i as u64), d)))
+    }
+}
+
+// ============================ SparseSecondaryMap 核心实现（稀疏存储） ============================
+/// 稀疏辅助表：HashMap 存储，适合只有少数 Id 附有数据的场景，接口与 SecondaryMap 一致
+#[derive(Debug, Clone)]
+pub struct SparseSecondaryMap<K: Id, D> {
+    inner: HashMap<u64, D>, // 底层存储：u64 -> D
+    _marker: PhantomData<K>,
+}
+
+impl<K: Id, D> Default for SparseSecondaryMap<K, D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Id, D> SparseSecondaryMap<K, D> {
+    /// 创建空的 SparseSecondaryMap
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+    
+    /// 创建指定初始容量的 SparseSecondaryMap
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: HashMap::with_capacity(capacity),
+            _marker: PhantomData,
+        }
+    }
+    
+    /// 为 Id 附加数据，返回旧数据（若存在）
+    pub fn insert(&mut self, id: K, data: D) -> Option<D> {
+        self.inner.insert(id.as_u64(), data)
+    }
+    
+    /// 根据 Id 查询数据
+    pub fn get(&self, id: K) -> Option<&D> {
+        self.inner.get(&id.as_u64())
+    }
+    
+    /// 根据 Id 查询可变数据
+    pub fn get_mut(&mut self, id: K) -> Option<&mut D> {
+        self.inner.get_mut(&id.as_u64())
+    }
+    
+    /// 根据 Id 删除数据
+    pub fn remove(&mut self, id: K) -> Option<D> {
+        self.inner.remove(&id.as_u64())
+    }
+    
+    /// 判断 Id 是否附有数据
+    pub fn contains_id(&self, id: K) -> bool {
+        self.inner.contains_key(&id.as_u64())
+    }
+    
+    /// 获取元素数量
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+    
+    /// 判断是否为空
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+    
+    /// 清空所有数据
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+    
+    /// 遍历 `(K, &D)`（顺序不保证）
+    pub fn iter(&self) -> impl Iterator<Item = (K, &D)> {
+        self.inner.iter().map(|(id, d)| (K::from_u64(*id), d))
+    }
+    
+    /// 遍历 `(K, &mut D)`（顺序不保证）
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (K, &mut D)> {
+        self.inner.iter_mut().map(|(id, d)| (K::from_u64(*id), d))
+    }
+}
+
+// ============================ Index/IndexMut 实现 ============================
+impl<K: Id, D> Index<K> for SecondaryMap<K, D> {
+    type Output = D;
+    
+    fn index(&self, id: K) -> &Self::Output {
+        self.get(id).expect("invalid SecondaryMap id")
+    }
+}
+
+impl<K: Id, D> IndexMut<K> for SecondaryMap<K, D> {
+    fn index_mut(&mut self, id: K) -> &mut Self::Output {
+        self.get_mut(id).expect("invalid SecondaryMap id")
+    }
+}
+
+impl<K: Id, D> Index<K> for SparseSecondaryMap<K, D> {
+    type Output = D;
+    
+    fn index(&self, id: K) -> &Self::Output {
+        self.get(id).expect("invalid SparseSecondaryMap id")
+    }
+}
+
+impl<K: Id, D> IndexMut<K> for SparseSecondaryMap<K, D> {
+    fn index_mut(&mut self, id: K) -> &mut Self::Output {
+        self.get_mut(id).expect("invalid SparseSecondaryMap id")
+    }
+}
+
+// ============================ 测试用例 ============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DefaultId, IdMap};
+    
+    #[test]
+    fn test_secondary_map() {
+        let mut ids = IdMap::new();
+        let id1 = ids.insert("a");
+        let id2 = ids.insert("b");
+        let id3 = ids.insert("c");
+        
+        let mut sec = SecondaryMap::<DefaultId, u32>::new();
+        assert_eq!(sec.insert(id3, 30), None);
+        assert_eq!(sec.insert(id1, 10), None);
+        assert_eq!(sec.insert(id1, 11), Some(10));
+        
+        assert_eq!(sec.len(), 2);
+        assert_eq!(sec.get(id1), Some(&11));
+        assert_eq!(sec.get(id2), None);
+        assert_eq!(sec[id3], 30);
+        
+        // 按 Id 升序遍历
+        let all: Vec<_> = sec.iter().map(|(id, d)| (id, *d)).collect();
+        assert_eq!(all, vec![(id1, 11), (id3, 30)]);
+        
+        assert_eq!(sec.remove(id1), Some(11));
+        assert!(!sec.contains_id(id1));
+        assert_eq!(sec.len(), 1);
+    }
+    
+    #[test]
+    fn test_sparse_secondary_map() {
+        let mut sec = SparseSecondaryMap::<DefaultId, &str>::new();
+        let far = DefaultId(1_000_000);
+        assert_eq!(sec.insert(far, "far"), None);
+        assert_eq!(sec.insert(far, "far2"), Some("far"));
+        
+        assert_eq!(sec.len(), 1);
+        assert_eq!(sec[far], "far2");
+        assert_eq!(sec.remove(far), Some("far2"));
+        assert!(sec.is_empty());
+    }
+}
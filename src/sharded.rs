@@ -0,0 +1,291 @@
+//! 分片的 OrdIdMap：把有限 span 切成若干段，每段单独加锁，按 field 值路由，供多线程并发写入
+
+use std::iter::successors;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use field_collex::{Collexetable, FieldValue};
+use field_collex::collex::InsertFieldCollexError;
+use span_core::Span;
+use crate::{Id, IdGen, ModifyManyError, NewShardedError, OrdIdMap, ReplaceError};
+
+/// 各段起始值、span 终点与各段对应的空 OrdIdMap
+pub(crate) type Segments<K, E, V> = (Vec<V>, V, Vec<OrdIdMap<K, E, V>>);
+
+type WriteGuard<'a, K, E, V> = RwLockWriteGuard<'a, OrdIdMap<K, E, V>>;
+
+/// 把有限 span 按 unit 对齐切成至多 `count` 段
+pub(crate) fn split_span<K, E, V>(
+    span: Span<V>,
+    unit: V,
+    count: usize,
+) -> Result<Segments<K, E, V>, NewShardedError<V>>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    if count == 0 {
+        return Err(NewShardedError::NoShards);
+    }
+    let Span::Finite(range) = &span else {
+        return Err(NewShardedError::Unbounded);
+    };
+    let (start, end) = (range.start, range.end);
+    // 先以完整 span 校验一次，之后合并回单个 OrdIdMap 时不会再失败
+    OrdIdMap::<K, E, V>::new(span, unit).map_err(NewShardedError::Invalid)?;
+    
+    let slots: Vec<V> = successors(Some(start), |&s| Some(s + unit))
+        .take_while(|&s| s < end)
+        .collect();
+    let per_segment = slots.len().div_ceil(count).max(1);
+    let starts: Vec<V> = slots.iter().copied().step_by(per_segment).collect();
+    let maps = starts
+        .iter()
+        .enumerate()
+        .map(|(i, &s)| {
+            let e = starts.get(i + 1).copied().unwrap_or(end);
+            OrdIdMap::new(Span::new_finite(s, e), unit).map_err(NewShardedError::Invalid)
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((starts, end, maps))
+}
+
+/// field 值所在的段，超出 `[starts[0], end)` 返回 None
+pub(crate) fn segment_index<V: FieldValue>(starts: &[V], end: V, value: &V) -> Option<usize> {
+    if *value < starts[0] || *value >= end {
+        return None;
+    }
+    Some(starts.partition_point(|s| s <= value) - 1)
+}
+
+/// 分片的 OrdIdMap
+///
+/// 各分片的 span 是总 span 中按 unit 对齐的连续一段；Id 由共享的 `IdGen` 统一生成，跨分片不重复。
+/// 按 field 值的操作只锁一个分片，按 Id 的操作需要依次查找各分片
+#[derive(Debug)]
+pub struct ShardedOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    /// 各分片的起始值，升序
+    starts: Vec<V>,
+    end: V,
+    unit: V,
+    shards: Vec<RwLock<OrdIdMap<K, E, V>>>,
+    id_gen: IdGen<K>,
+}
+
+impl<K, E, V> ShardedOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    /// 把 span 按 unit 对齐切成至多 `shard_count` 段（槽位不足时分片数会更少）
+    pub fn new(span: Span<V>, unit: V, shard_count: usize) -> Result<Self, NewShardedError<V>> {
+        let (starts, end, maps) = split_span(span, unit, shard_count)?;
+        Ok(Self {
+            starts,
+            end,
+            unit,
+            shards: maps.into_iter().map(RwLock::new).collect(),
+            id_gen: IdGen::new(K::from_u64(0)),
+        })
+    }
+    
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+    
+    /// field 值所属的分片，超出 span 返回 None
+    pub fn shard_index(&self, value: &V) -> Option<usize> {
+        segment_index(&self.starts, self.end, value)
+    }
+    
+    /// 持读锁访问单个分片
+    pub fn read_shard(&self, index: usize) -> RwLockReadGuard<'_, OrdIdMap<K, E, V>> {
+        self.shards[index].read().expect("ShardedOrdIdMap lock poisoned")
+    }
+    
+    /// 持写锁访问单个分片
+    ///
+    /// 注意：直接在分片上自动生成 Id 的插入会绕过共享的 IdGen，应改用 `insert_with_id`
+    pub fn write_shard(&self, index: usize) -> RwLockWriteGuard<'_, OrdIdMap<K, E, V>> {
+        self.shards[index].write().expect("ShardedOrdIdMap lock poisoned")
+    }
+    
+    /// 按索引升序对两个不同的分片加写锁，返回顺序与参数顺序一致
+    fn write_pair(&self, a: usize, b: usize) -> (WriteGuard<'_, K, E, V>, WriteGuard<'_, K, E, V>) {
+        if a < b {
+            let first = self.write_shard(a);
+            (first, self.write_shard(b))
+        } else {
+            let first = self.write_shard(b);
+            (self.write_shard(a), first)
+        }
+    }
+    
+    /// 元素所在的分片
+    fn shard_of_id(&self, id: K) -> Option<usize> {
+        (0..self.shards.len()).find(|&i| self.read_shard(i).contains_id(id))
+    }
+    
+    /// 插入元素，只锁定其 field 值所属的分片
+    ///
+    /// 插入失败时已领取的 Id 不会回收
+    pub fn insert(&self, elem: E) -> Result<K, InsertFieldCollexError<E>> {
+        let Some(index) = self.shard_index(elem.collexate_ref()) else {
+            return Err(InsertFieldCollexError::OutOfSpan(elem));
+        };
+        let id = self.id_gen.next();
+        self.write_shard(index).insert_with_id(id, elem).map(|_| id)
+    }
+    
+    pub fn remove(&self, id: K) -> Option<E> {
+        self.shards
+            .iter()
+            .find_map(|shard| shard.write().expect("ShardedOrdIdMap lock poisoned").remove(id))
+    }
+    
+    /// 修改元素，field 值离开原分片时会移到新的分片
+    ///
+    /// 闭包作用在元素的克隆上，校验通过后才写回；若期间该分片被其他线程修改，闭包会被重新调用。
+    /// 失败时元素保持修改前的值
+    pub fn modify<F, R>(&self, id: K, mut f: F) -> Result<R, ModifyManyError>
+    where
+        E: Clone,
+        F: FnMut(&mut E) -> R,
+    {
+        loop {
+            let src = self.shard_of_id(id).ok_or(ModifyManyError::CannotFind)?;
+            let (version, r, elem) = {
+                let shard = self.read_shard(src);
+                let Some(current) = shard.get_with_id(id) else { continue };
+                let mut elem = current.clone();
+                let r = f(&mut elem);
+                (shard.version(), r, elem)
+            };
+            let dst = self.shard_index(elem.collexate_ref()).ok_or(ModifyManyError::OutOfSpan)?;
+            
+            if src == dst {
+                let mut shard = self.write_shard(src);
+                if shard.version() != version {
+                    continue;
+                }
+                return shard.replace(id, elem).map(|_| r).map_err(|err| match err {
+                    ReplaceError::CannotFind(_) => ModifyManyError::CannotFind,
+                    ReplaceError::OutOfSpan(_) => ModifyManyError::OutOfSpan,
+                    ReplaceError::AlreadyExist(_) => ModifyManyError::AlreadyExist,
+                });
+            }
+            
+            let (mut from, mut to) = self.write_pair(src, dst);
+            if from.version() != version {
+                continue;
+            }
+            return match to.insert_with_id(id, elem) {
+                Ok(_) => {
+                    from.remove(id);
+                    Ok(r)
+                }
+                Err(InsertFieldCollexError::OutOfSpan(_)) => Err(ModifyManyError::OutOfSpan),
+                Err(InsertFieldCollexError::AlreadyExist(_)) => Err(ModifyManyError::AlreadyExist),
+            };
+        }
+    }
+    
+    /// 按 Id 查询，返回元素的克隆
+    pub fn get_with_id(&self, id: K) -> Option<E>
+    where
+        E: Clone,
+    {
+        self.with(id, E::clone)
+    }
+    
+    /// 按 Id 查询，在读锁内对元素执行闭包
+    pub fn with<F, R>(&self, id: K, f: F) -> Option<R>
+    where
+        F: FnOnce(&E) -> R,
+    {
+        let index = self.shard_of_id(id)?;
+        self.read_shard(index).get_with_id(id).map(f)
+    }
+    
+    /// 按 field 值查询 Id，只锁定一个分片
+    pub fn id_at(&self, value: V) -> Option<K> {
+        let index = self.shard_index(&value)?;
+        self.read_shard(index).id_at(value)
+    }
+    
+    pub fn len(&self) -> usize {
+        (0..self.shards.len()).map(|i| self.read_shard(i).len()).sum()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        (0..self.shards.len()).all(|i| self.read_shard(i).is_empty())
+    }
+    
+    /// 合并回单个覆盖完整 span 的 OrdIdMap，Id 保持不变
+    pub fn merge_into_single(self) -> OrdIdMap<K, E, V> {
+        let Ok(mut map) = OrdIdMap::new(Span::new_finite(self.starts[0], self.end), self.unit) else {
+            unreachable!("span validated in ShardedOrdIdMap::new")
+        };
+        let total = self.len();
+        map.reserve(total);
+        for shard in self.shards {
+            let shard = shard.into_inner().expect("ShardedOrdIdMap lock poisoned");
+            for (id, elem) in shard.into_vec() {
+                if map.insert_with_id(id, elem).is_err() {
+                    panic!("shard element does not fit the merged map");
+                }
+            }
+        }
+        map.sync_id_gen(&self.id_gen);
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use span_core::Span;
+    use crate::{DefaultId, ModifyManyError};
+    use super::ShardedOrdIdMap;
+    use crate::test_util::TestO;
+    
+    #[test]
+    fn test_sharded_ingest_and_merge() {
+        let sharded: ShardedOrdIdMap<DefaultId, TestO, u32> =
+            ShardedOrdIdMap::new(Span::new_finite(0u32, 1000u32), 10, 4).unwrap();
+        assert_eq!(sharded.shard_count(), 4);
+        assert_eq!(sharded.shard_index(&0), Some(0));
+        assert_eq!(sharded.shard_index(&999), Some(3));
+        assert_eq!(sharded.shard_index(&1000), None);
+        
+        thread::scope(|s| {
+            for t in 0..4u32 {
+                let sharded = &sharded;
+                s.spawn(move || {
+                    for i in 0..10u32 {
+                        sharded.insert(TestO((t * 10 + i) * 20)).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(sharded.len(), 40);
+        
+        // 跨分片移动
+        let id = sharded.id_at(0).unwrap();
+        sharded.modify(id, |e| e.0 = 990).unwrap();
+        assert_eq!(sharded.get_with_id(id), Some(TestO(990)));
+        assert_eq!(sharded.id_at(0), None);
+        assert_eq!(sharded.modify(id, |e| e.0 = 20), Err(ModifyManyError::AlreadyExist));
+        assert_eq!(sharded.get_with_id(id), Some(TestO(990)));
+        
+        let mut map = sharded.merge_into_single();
+        assert_eq!(map.len(), 40);
+        assert!(map.validate().is_ok());
+        assert_eq!(map.insert(TestO(995)).unwrap(), DefaultId(41));
+    }
+}
@@ -0,0 +1,153 @@
+//! 多线程共享：Arc<RwLock<OrdIdMap>> 的封装，按 Id 操作，每次调用只短暂持锁
+
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use field_collex::{Collexetable, FieldValue};
+use field_collex::collex::{InsertFieldCollexError, ModifyFieldCollexError};
+use crate::{Id, OrdIdMap};
+
+/// 可廉价克隆的共享 OrdIdMap 句柄，所有克隆指向同一份数据
+#[derive(Debug)]
+pub struct SharedOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    inner: Arc<RwLock<OrdIdMap<K, E, V>>>,
+}
+
+impl<K, E, V> Clone for SharedOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<K, E, V> From<OrdIdMap<K, E, V>> for SharedOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    fn from(map: OrdIdMap<K, E, V>) -> Self {
+        Self::new(map)
+    }
+}
+
+impl<K, E, V> SharedOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    pub fn new(map: OrdIdMap<K, E, V>) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(map)),
+        }
+    }
+    
+    /// 持读锁访问整个 OrdIdMap，用于需要多次查询保持一致的场景
+    pub fn read(&self) -> RwLockReadGuard<'_, OrdIdMap<K, E, V>> {
+        self.inner.read().expect("SharedOrdIdMap lock poisoned")
+    }
+    
+    /// 持写锁访问整个 OrdIdMap，用于需要多个操作原子生效的场景
+    pub fn write(&self) -> RwLockWriteGuard<'_, OrdIdMap<K, E, V>> {
+        self.inner.write().expect("SharedOrdIdMap lock poisoned")
+    }
+    
+    pub fn insert(&self, elem: E) -> Result<K, InsertFieldCollexError<E>> {
+        self.write().insert(elem)
+    }
+    
+    pub fn insert_with_id(&self, id: K, elem: E) -> Result<Option<E>, InsertFieldCollexError<E>> {
+        self.write().insert_with_id(id, elem)
+    }
+    
+    pub fn remove(&self, id: K) -> Option<E> {
+        self.write().remove(id)
+    }
+    
+    pub fn modify<F, R>(&self, id: K, f: F) -> Result<R, ModifyFieldCollexError<(R, E)>>
+    where
+        F: FnOnce(&mut E) -> R,
+    {
+        self.write().modify(id, f)
+    }
+    
+    /// 按 Id 查询，返回元素的克隆，不在锁外暴露引用
+    pub fn get_with_id(&self, id: K) -> Option<E>
+    where
+        E: Clone,
+    {
+        self.read().get_with_id(id).cloned()
+    }
+    
+    /// 按 Id 查询，在读锁内对元素执行闭包
+    pub fn with<F, R>(&self, id: K, f: F) -> Option<R>
+    where
+        F: FnOnce(&E) -> R,
+    {
+        self.read().get_with_id(id).map(f)
+    }
+    
+    pub fn contains_id(&self, id: K) -> bool {
+        self.read().contains_id(id)
+    }
+    
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+    
+    /// 若当前句柄是唯一的，取回内部的 OrdIdMap；否则原样返回句柄
+    pub fn try_unwrap(self) -> Result<OrdIdMap<K, E, V>, Self> {
+        Arc::try_unwrap(self.inner)
+            .map(|lock| lock.into_inner().expect("SharedOrdIdMap lock poisoned"))
+            .map_err(|inner| Self { inner })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use span_core::Span;
+    use crate::{DefaultId, OrdIdMap};
+    use super::SharedOrdIdMap;
+    use crate::test_util::TestO;
+    
+    #[test]
+    fn test_shared_across_threads() {
+        let map: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::new(Span::new_finite(0u32, 1000u32), 10).unwrap();
+        let shared = SharedOrdIdMap::new(map);
+        
+        let handles: Vec<_> = (0..4u32)
+            .map(|t| {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    (0..10u32)
+                        .map(|i| shared.insert(TestO((t * 10 + i) * 10)).unwrap())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let ids: Vec<_> = handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+        
+        assert_eq!(shared.len(), 40);
+        shared.modify(ids[0], |e| e.0 = 995).unwrap();
+        assert_eq!(shared.get_with_id(ids[0]), Some(TestO(995)));
+        assert!(shared.remove(ids[1]).is_some());
+        
+        let map = shared.try_unwrap().unwrap();
+        assert_eq!(map.len(), 39);
+    }
+}
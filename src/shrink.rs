@@ -0,0 +1,68 @@
+//! 大量删除后释放多余容量的策略
+
+use field_collex::{Collexetable, FieldValue};
+use crate::{Id, OrdIdMap};
+
+/// remove、retain、drain 等删除操作之后是否调用 `shrink_to_fit`
+///
+/// 收缩需重建整个 collex，耗时 O(n)；频繁的单个删除不宜搭配 `Always`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ShrinkPolicy {
+    /// 从不自动收缩，容量保持历史峰值
+    #[default]
+    Never,
+    /// collex 已用块数低于其容量的给定比例时收缩
+    WhenBelow(f64),
+    /// 每次删除后都收缩
+    Always,
+}
+
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    pub fn shrink_policy(&self) -> ShrinkPolicy {
+        self.shrink_policy
+    }
+    
+    /// 设置删除后的收缩策略，只影响之后的删除操作
+    pub fn set_shrink_policy(&mut self, policy: ShrinkPolicy) {
+        self.shrink_policy = policy;
+    }
+    
+    /// 按收缩策略在删除后释放多余容量
+    pub(crate) fn apply_shrink_policy(&mut self) {
+        let shrink = match self.shrink_policy {
+            ShrinkPolicy::Never => false,
+            ShrinkPolicy::WhenBelow(ratio) => (self.collex.len() as f64) < self.collex.capacity() as f64 * ratio,
+            ShrinkPolicy::Always => true,
+        };
+        if shrink {
+            self.shrink_to_fit();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use span_core::Span;
+    use crate::{DefaultId, OrdIdMap, ShrinkPolicy};
+    use crate::test_util::TestO;
+    
+    #[test]
+    fn test_shrink_policy() {
+        let mut map: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::new(Span::new_finite(0u32, 1000u32), 1).unwrap();
+        map.extend((0..500u32).map(TestO));
+        let peak = map.capacity();
+        
+        map.retain(|_, e| e.0 < 400);
+        assert_eq!(map.capacity(), peak);
+        
+        map.set_shrink_policy(ShrinkPolicy::WhenBelow(0.5));
+        map.retain(|_, e| e.0 < 10);
+        assert!(map.capacity() < peak);
+        assert!(map.validate().is_ok());
+    }
+}
@@ -0,0 +1,222 @@
+//! 元素较少时不占用堆内存的 OrdIdMap：不超过 N 个元素时元素与 Id 都存放在内联数组中，超出后转为普通 OrdIdMap
+
+use field_collex::{Collexetable, FieldValue};
+use field_collex::collex::{InsertFieldCollexError, NewFieldCollexError};
+use span_core::Span;
+use crate::{Id, OrdIdMap, Pair};
+
+/// 内联存储：按 field 值升序排列的元素，`frame` 是只负责 span、unit 与 Id 分配的空 OrdIdMap
+#[derive(Debug)]
+struct Inline<K, E, V, const N: usize>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    frame: OrdIdMap<K, E, V>,
+    elems: [Option<Pair<K, E>>; N],
+    len: usize,
+}
+
+#[derive(Debug)]
+enum Repr<K, E, V, const N: usize>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    Inline(Inline<K, E, V, N>),
+    Heap(OrdIdMap<K, E, V>),
+}
+
+/// 小容量优化的 OrdIdMap
+///
+/// 元素不超过 `N` 个时既不分配 collex 的元素存储，也不写 id_map 的 HashMap，按 Id 的查找为线性扫描；
+/// 插入第 `N + 1` 个元素时一次性转为普通 OrdIdMap，之后即使删除元素也不再转回
+#[derive(Debug)]
+pub struct SmallOrdIdMap<K, E, V, const N: usize>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    repr: Repr<K, E, V, N>,
+}
+
+fn inline_elem<K: Id, E>(slot: &Option<Pair<K, E>>) -> &Pair<K, E> {
+    slot.as_ref().expect("inline slot within len")
+}
+
+impl<K, E, V, const N: usize> SmallOrdIdMap<K, E, V, N>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    pub fn new(span: Span<V>, unit: V) -> Result<Self, NewFieldCollexError<V>> {
+        Ok(Self {
+            repr: Repr::Inline(Inline {
+                frame: OrdIdMap::new(span, unit)?,
+                elems: [const { None }; N],
+                len: 0,
+            }),
+        })
+    }
+    
+    /// 是否仍处于内联存储
+    pub fn is_inline(&self) -> bool {
+        matches!(self.repr, Repr::Inline(_))
+    }
+    
+    pub fn insert(&mut self, elem: E) -> Result<K, InsertFieldCollexError<E>> {
+        let inline = match &mut self.repr {
+            Repr::Inline(inline) => inline,
+            Repr::Heap(map) => return map.insert(elem),
+        };
+        let v = elem.collexate();
+        if !inline.frame.span().contains(&v) {
+            return Err(InsertFieldCollexError::OutOfSpan(elem));
+        }
+        let live = &inline.elems[..inline.len];
+        let pos = live.partition_point(|slot| *inline_elem(slot).collexate_ref() < v);
+        if live.get(pos).is_some_and(|slot| *inline_elem(slot).collexate_ref() == v) {
+            return Err(InsertFieldCollexError::AlreadyExist(elem));
+        }
+        if inline.len == N {
+            self.spill();
+            return self.insert(elem);
+        }
+        
+        let id = inline.frame.id_map.next_id();
+        inline.elems[inline.len] = Some(Pair(id, elem));
+        inline.elems[pos..=inline.len].rotate_right(1);
+        inline.len += 1;
+        Ok(id)
+    }
+    
+    pub fn remove(&mut self, id: K) -> Option<E> {
+        match &mut self.repr {
+            Repr::Inline(inline) => {
+                let index = inline.elems[..inline.len].iter().position(|slot| inline_elem(slot).0 == id)?;
+                inline.elems[index..inline.len].rotate_left(1);
+                inline.len -= 1;
+                inline.elems[inline.len].take().map(|p| p.1)
+            }
+            Repr::Heap(map) => map.remove(id),
+        }
+    }
+    
+    pub fn get_with_id(&self, id: K) -> Option<&E> {
+        match &self.repr {
+            Repr::Inline(inline) => inline.elems[..inline.len]
+                .iter()
+                .map(inline_elem)
+                .find(|p| p.0 == id)
+                .map(|p| &p.1),
+            Repr::Heap(map) => map.get_with_id(id),
+        }
+    }
+    
+    pub fn id_at(&self, value: V) -> Option<K> {
+        match &self.repr {
+            Repr::Inline(inline) => inline.elems[..inline.len]
+                .iter()
+                .map(inline_elem)
+                .find(|p| *p.collexate_ref() == value)
+                .map(|p| p.0),
+            Repr::Heap(map) => map.id_at(value),
+        }
+    }
+    
+    pub fn contains_id(&self, id: K) -> bool {
+        self.get_with_id(id).is_some()
+    }
+    
+    /// 按 field 值升序遍历
+    pub fn iter(&self) -> impl Iterator<Item = (K, &E)> {
+        let (inline, heap) = match &self.repr {
+            Repr::Inline(inline) => (Some(&inline.elems[..inline.len]), None),
+            Repr::Heap(map) => (None, Some(map.iter())),
+        };
+        inline
+            .into_iter()
+            .flatten()
+            .map(|slot| {
+                let p = inline_elem(slot);
+                (p.0, &p.1)
+            })
+            .chain(heap.into_iter().flatten())
+    }
+    
+    pub fn len(&self) -> usize {
+        match &self.repr {
+            Repr::Inline(inline) => inline.len,
+            Repr::Heap(map) => map.len(),
+        }
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    
+    /// 转为普通 OrdIdMap，Id 保持不变
+    pub fn into_map(mut self) -> OrdIdMap<K, E, V> {
+        self.spill();
+        match self.repr {
+            Repr::Heap(map) => map,
+            Repr::Inline(_) => unreachable!(),
+        }
+    }
+    
+    /// 把内联的元素搬进 OrdIdMap，之后改用堆存储
+    fn spill(&mut self) {
+        let Repr::Inline(inline) = &mut self.repr else {
+            return;
+        };
+        let Ok(empty) = OrdIdMap::new(inline.frame.span().clone(), *inline.frame.unit()) else {
+            unreachable!("span validated in SmallOrdIdMap::new")
+        };
+        let mut map = std::mem::replace(&mut inline.frame, empty);
+        map.reserve(N + 1);
+        for slot in &mut inline.elems[..inline.len] {
+            let Pair(id, elem) = slot.take().expect("inline slot within len");
+            if map.insert_with_id(id, elem).is_err() {
+                panic!("inline element does not fit the spilled map");
+            }
+        }
+        self.repr = Repr::Heap(map);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use field_collex::collex::InsertFieldCollexError;
+    use span_core::Span;
+    use crate::DefaultId;
+    use super::SmallOrdIdMap;
+    use crate::test_util::TestO;
+    
+    #[test]
+    fn test_small_spill() {
+        let mut map: SmallOrdIdMap<DefaultId, TestO, u32, 2> =
+            SmallOrdIdMap::new(Span::new_finite(0u32, 100u32), 10).unwrap();
+        let a = map.insert(TestO(30)).unwrap();
+        let b = map.insert(TestO(10)).unwrap();
+        assert!(map.is_inline());
+        assert!(matches!(map.insert(TestO(10)), Err(InsertFieldCollexError::AlreadyExist(_))));
+        assert!(matches!(map.insert(TestO(100)), Err(InsertFieldCollexError::OutOfSpan(_))));
+        assert_eq!(map.iter().map(|(id, _)| id).collect::<Vec<_>>(), vec![b, a]);
+        assert_eq!(map.id_at(30), Some(a));
+        
+        let c = map.insert(TestO(20)).unwrap();
+        assert!(!map.is_inline());
+        assert_eq!(c, DefaultId(3));
+        assert_eq!(map.iter().map(|(id, _)| id).collect::<Vec<_>>(), vec![b, c, a]);
+        assert_eq!(map.remove(b), Some(TestO(10)));
+        assert_eq!(map.get_with_id(a), Some(&TestO(30)));
+        
+        let map = map.into_map();
+        assert_eq!(map.len(), 2);
+        assert!(map.validate().is_ok());
+    }
+}
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+use field_collex::{Collexetable, FieldValue};
+use crate::{Id, OrdIdMap};
+
+/// OrdIdMap 的完整状态快照（含 max_id），用于撤销试探性的修改
+///
+/// 拍摄快照会完整复制一次 OrdIdMap，代价为 O(n)；之后克隆快照本身是 O(1) 的，仅被唯一持有时 `restore` 不会再次复制。
+/// 需要频繁拍摄快照时改用 `CowOrdIdMap`：其克隆为 O(1)，克隆本身即可作为快照
+#[derive(Debug, Clone)]
+pub struct Snapshot<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    state: Arc<OrdIdMap<K, E, V>>,
+}
+
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V> + Clone,
+    V: FieldValue,
+{
+    /// 拍摄当前状态的快照，复制全部元素与 id_map，O(n)
+    pub fn snapshot(&self) -> Snapshot<K, E, V> {
+        Snapshot {
+            state: Arc::new(self.clone()),
+        }
+    }
+    
+    /// 恢复到快照时的状态，包括 max_id
+    pub fn restore(&mut self, snap: Snapshot<K, E, V>) {
+        *self = Arc::unwrap_or_clone(snap.state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::{TestO, new_map};
+    
+    #[test]
+    fn test_snapshot_restore() {
+        let mut map = new_map();
+        let a = map.insert(TestO(10)).unwrap();
+        let snap = map.snapshot();
+        
+        map.remove(a);
+        let b = map.insert(TestO(20)).unwrap();
+        map.restore(snap);
+        
+        assert_eq!(map.get_with_id(a), Some(&TestO(10)));
+        assert!(!map.contains_id(b));
+        // max_id 一并恢复
+        assert_eq!(map.max_id(), a);
+    }
+}
@@ -0,0 +1,135 @@
+//! 占用情况统计，用于容量规划与判断何时整理碎片
+
+use field_collex::{Collexetable, FieldValue};
+use crate::alloc::cell_starts;
+use crate::{Id, OrdIdMap};
+
+/// 一个统计桶 `[start, start + bucket_size)` 内的元素数量
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bucket<V> {
+    pub start: V,
+    pub count: usize,
+}
+
+/// `stats` 的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct OccupancyStats<V> {
+    /// 元素数量
+    pub len: usize,
+    /// 最小与最大的 field 值，为空时为 None
+    pub occupied: Option<(V, V)>,
+    /// 从 span 起点按桶大小划分的各桶元素数量；span 无界时为空
+    pub buckets: Vec<Bucket<V>>,
+    /// 空闲槽位总数
+    pub free_slots: usize,
+    /// 最长的连续空闲槽位数
+    pub largest_free_run: usize,
+}
+
+impl<V> OccupancyStats<V> {
+    /// 碎片率：`1 - 最长连续空闲槽位数 / 空闲槽位总数`，没有空闲槽位时为 0
+    ///
+    /// 为 0 表示空闲空间连成一片，越接近 1 越零碎
+    pub fn fragmentation(&self) -> f64 {
+        if self.free_slots == 0 {
+            return 0.0;
+        }
+        1.0 - self.largest_free_run as f64 / self.free_slots as f64
+    }
+}
+
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    /// 统计占用情况，`bucket_size` 为直方图每个桶的宽度
+    ///
+    /// `bucket_size` 不为正时 panic
+    pub fn stats(&self, bucket_size: V) -> OccupancyStats<V> {
+        assert!(bucket_size > V::zero(), "bucket size must be positive");
+        let occupied = self.collex
+            .iter()
+            .next()
+            .zip(self.collex.iter().last())
+            .map(|(min, max)| (min.collexate(), max.collexate()));
+        
+        let mut buckets = Vec::new();
+        if let Some((start, end)) = self.finite_bounds() {
+            let mut values = self.collex.iter().map(|p| p.collexate()).peekable();
+            for s in cell_starts(start, end, bucket_size) {
+                let mut count = 0;
+                while values.next_if(|&v| v - s < bucket_size).is_some() {
+                    count += 1;
+                }
+                buckets.push(Bucket { start: s, count });
+            }
+        }
+        
+        let (mut free_slots, mut largest_free_run, mut run) = (0, 0, 0);
+        for (_, occ) in self.slots().into_iter().flatten() {
+            if occ {
+                run = 0;
+            } else {
+                free_slots += 1;
+                run += 1;
+                largest_free_run = largest_free_run.max(run);
+            }
+        }
+        
+        OccupancyStats {
+            len: self.len(),
+            occupied,
+            buckets,
+            free_slots,
+            largest_free_run,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use span_core::Span;
+    use crate::{Bucket, DefaultId, OrdIdMap};
+    use crate::test_util::{TestO, new_map};
+    
+    #[test]
+    fn test_stats() {
+        let mut map = new_map();
+        for v in [0, 10, 30, 60] {
+            map.insert(TestO(v)).unwrap();
+        }
+        
+        let stats = map.stats(50);
+        assert_eq!(stats.len, 4);
+        assert_eq!(stats.occupied, Some((0, 60)));
+        assert_eq!(stats.buckets, vec![
+            Bucket { start: 0, count: 3 },
+            Bucket { start: 50, count: 1 },
+        ]);
+        // 空闲槽位：20、40、50、70、80、90
+        assert_eq!(stats.free_slots, 6);
+        assert_eq!(stats.largest_free_run, 3);
+        assert!((stats.fragmentation() - 0.5).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_stats_near_max() {
+        let mut map: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::new(Span::new_finite(0u32, u32::MAX), 1 << 30).unwrap();
+        map.insert(TestO(u32::MAX - 1)).unwrap();
+        
+        // 最后一个桶的 `start + bucket_size` 超出 u32
+        let stats = map.stats(3_000_000_000);
+        assert_eq!(stats.buckets, vec![
+            Bucket { start: 0, count: 0 },
+            Bucket { start: 3_000_000_000, count: 1 },
+        ]);
+    }
+    
+    #[test]
+    #[should_panic(expected = "bucket size must be positive")]
+    fn test_stats_zero_bucket() {
+        let _ = new_map().stats(0);
+    }
+}
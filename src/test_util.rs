@@ -0,0 +1,26 @@
+//! 各模块测试共用的元素类型与构造函数
+
+use field_collex::Collexetable;
+use serde::{Deserialize, Serialize};
+use span_core::Span;
+use crate::{DefaultId, OrdIdMap};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestO(pub u32);
+pub type TestT = u32;
+impl Collexetable<TestT> for TestO {
+    fn collexate(&self) -> TestT { self.0 }
+    
+    fn collexate_ref(&self) -> &TestT {
+        &self.0
+    }
+    
+    fn collexate_mut(&mut self) -> &mut TestT {
+        &mut self.0
+    }
+}
+
+/// span 为 0..100、unit 为 10 的空 OrdIdMap
+pub fn new_map() -> OrdIdMap<DefaultId, TestO, TestT> {
+    OrdIdMap::new(Span::new_finite(0u32, 100u32), 10).unwrap()
+}
@@ -0,0 +1,151 @@
+use std::ops::Deref;
+use field_collex::{Collexetable, FieldValue};
+use field_collex::collex::{InsertFieldCollexError, ModifyFieldCollexError};
+use crate::{Id, OrdIdMap};
+
+/// 回滚时需要执行的逆操作
+enum Undo<K, E> {
+    /// 删除事务中插入的 Id
+    Remove(K),
+    /// 把 Id 恢复为事务前的元素
+    Restore(K, E),
+}
+
+/// 事务句柄：在 `OrdIdMap::transaction` 的闭包中执行的修改，出错时全部回滚
+///
+/// 只读访问通过 Deref 直接使用内部的 OrdIdMap
+pub struct Txn<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V> + Clone,
+    V: FieldValue,
+{
+    map: &'a mut OrdIdMap<K, E, V>,
+    log: Vec<Undo<K, E>>,
+}
+
+impl<'a, K, E, V> Deref for Txn<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V> + Clone,
+    V: FieldValue,
+{
+    type Target = OrdIdMap<K, E, V>;
+    fn deref(&self) -> &Self::Target {
+        self.map
+    }
+}
+
+impl<'a, K, E, V> Txn<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V> + Clone,
+    V: FieldValue,
+{
+    pub fn insert(&mut self, elem: E) -> Result<K, InsertFieldCollexError<E>> {
+        let id = self.map.insert(elem)?;
+        self.log.push(Undo::Remove(id));
+        Ok(id)
+    }
+    
+    pub fn insert_with_id(&mut self, id: K, elem: E) -> Result<Option<E>, InsertFieldCollexError<E>> {
+        let old = self.map.insert_with_id(id, elem)?;
+        self.log.push(match &old {
+            Some(old) => Undo::Restore(id, old.clone()),
+            None => Undo::Remove(id),
+        });
+        Ok(old)
+    }
+    
+    pub fn remove(&mut self, id: K) -> Option<E> {
+        let elem = self.map.remove(id)?;
+        self.log.push(Undo::Restore(id, elem.clone()));
+        Some(elem)
+    }
+    
+    pub fn modify<F,R>(&mut self, id: K, f: F) -> Result<R, ModifyFieldCollexError<(R,E)>>
+    where
+        F: FnOnce(&mut E) -> R,
+    {
+        if let Some(before) = self.map.get_with_id(id) {
+            self.log.push(Undo::Restore(id, before.clone()));
+        }
+        self.map.modify(id, f)
+    }
+    
+    /// 按记录逆序撤销所有修改
+    fn rollback(self) {
+        for undo in self.log.into_iter().rev() {
+            match undo {
+                Undo::Remove(id) => {
+                    self.map.remove(id);
+                }
+                Undo::Restore(id, elem) => {
+                    let _ = self.map.insert_with_id(id, elem);
+                }
+            }
+        }
+    }
+}
+
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V> + Clone,
+    V: FieldValue,
+{
+    /// 以事务方式执行一批修改
+    ///
+    /// 闭包返回 Ok 时全部生效；返回 Err 时所有插入、删除、修改连同 max_id 一并回滚
+    pub fn transaction<F, R, Err>(&mut self, f: F) -> Result<R, Err>
+    where
+        F: FnOnce(&mut Txn<'_, K, E, V>) -> Result<R, Err>,
+    {
+        let max_id = self.id_map.max_id();
+        let mut txn = Txn {
+            map: self,
+            log: Vec::new(),
+        };
+        match f(&mut txn) {
+            Ok(r) => Ok(r),
+            Err(err) => {
+                txn.rollback();
+                self.id_map.set_max_id(max_id);
+                Err(err)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::{TestO, new_map};
+    
+    #[test]
+    fn test_transaction_rollback() {
+        let mut map = new_map();
+        let a = map.insert(TestO(10)).unwrap();
+        let b = map.insert(TestO(20)).unwrap();
+        
+        let result: Result<(), &str> = map.transaction(|txn| {
+            txn.insert(TestO(30)).unwrap();
+            txn.remove(a);
+            txn.modify(b, |e| e.0 = 50).unwrap();
+            Err("abort")
+        });
+        
+        assert_eq!(result, Err("abort"));
+        assert_eq!(map.get_with_id(a), Some(&TestO(10)));
+        assert_eq!(map.get_with_id(b), Some(&TestO(20)));
+        assert_eq!(map.id_map.len(), 2);
+        assert_eq!(map.id_map.max_id(), b);
+    }
+    
+    #[test]
+    fn test_transaction_commit() {
+        let mut map = new_map();
+        
+        let id = map.transaction(|txn| txn.insert(TestO(30)).map_err(|_| ())).unwrap();
+        assert_eq!(map.get_with_id(id), Some(&TestO(30)));
+    }
+}
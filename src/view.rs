@@ -0,0 +1,122 @@
+use field_collex::{Collexetable, FieldValue};
+use field_collex::collex::InsertFieldCollexError;
+use span_core::Span;
+use crate::{Id, OrdIdMap};
+
+/// OrdIdMap 在子区间上的只读视图，查询与遍历只涉及 field 值落在区间内的元素
+pub struct SubView<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    map: &'a OrdIdMap<K, E, V>,
+    range: Span<V>,
+}
+
+/// OrdIdMap 在子区间上的可变视图，额外限制插入与删除只能发生在区间内
+pub struct SubViewMut<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    map: &'a mut OrdIdMap<K, E, V>,
+    range: Span<V>,
+}
+
+impl<'a, K, E, V> SubView<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    pub(crate) fn new(map: &'a OrdIdMap<K, E, V>, range: Span<V>) -> Self {
+        Self { map, range }
+    }
+    
+    pub fn range(&self) -> &Span<V> {
+        &self.range
+    }
+    
+    /// 按 field 顺序遍历区间内的 `(K, &E)`
+    pub fn iter(&self) -> impl Iterator<Item = (K, &'a E)> {
+        self.map.ids_in_range(self.range.clone())
+    }
+    
+    pub fn ids(&self) -> impl Iterator<Item = K> {
+        self.iter().map(|(id, _)| id)
+    }
+    
+    pub fn values(&self) -> impl Iterator<Item = &'a E> {
+        self.iter().map(|(_, e)| e)
+    }
+    
+    /// 按 Id 查询，元素不在区间内时视为不存在
+    pub fn get_with_id(&self, id: K) -> Option<&'a E> {
+        self.map
+            .get_with_id(id)
+            .filter(|e| self.range.contains(e.collexate_ref()))
+    }
+    
+    pub fn contains_id(&self, id: K) -> bool {
+        self.get_with_id(id).is_some()
+    }
+    
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+impl<'a, K, E, V> SubViewMut<'a, K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    pub(crate) fn new(map: &'a mut OrdIdMap<K, E, V>, range: Span<V>) -> Self {
+        Self { map, range }
+    }
+    
+    /// 转为只读视图
+    pub fn as_view(&self) -> SubView<'_, K, E, V> {
+        SubView::new(&*self.map, self.range.clone())
+    }
+    
+    /// 插入元素，field 值不在区间内时视为越界
+    pub fn insert(&mut self, elem: E) -> Result<K, InsertFieldCollexError<E>> {
+        if !self.range.contains(elem.collexate_ref()) {
+            return Err(InsertFieldCollexError::OutOfSpan(elem));
+        }
+        self.map.insert(elem)
+    }
+    
+    /// 删除区间内的元素，区间外的 Id 视为不存在
+    pub fn remove(&mut self, id: K) -> Option<E> {
+        if !self.as_view().contains_id(id) {
+            return None;
+        }
+        self.map.remove(id)
+    }
+}
+
+impl<K, E, V> OrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    /// 子区间上的只读视图
+    pub fn view(&self, range: Span<V>) -> SubView<'_, K, E, V> {
+        SubView::new(self, range)
+    }
+    
+    /// 子区间上的可变视图
+    pub fn view_mut(&mut self, range: Span<V>) -> SubViewMut<'_, K, E, V> {
+        SubViewMut::new(self, range)
+    }
+}
@@ -0,0 +1,220 @@
+//! 预写日志（WAL）：每个写操作先追加到磁盘日志并落盘，再作用到内存中的 OrdIdMap
+//!
+//! 日志文件第一行是完整状态快照（含 max_id），之后每行一条操作。
+//! 启动时用 `recover` 重放日志即可恢复崩溃前的状态；`checkpoint` 把当前状态写成新快照并清空操作
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use field_collex::{Collexetable, FieldValue};
+use field_collex::collex::InsertFieldCollexError;
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use crate::{Id, ModifyManyError, OrdIdMap, PersistError, ReplaceError};
+use crate::full::{as_full, FullOrdIdMap};
+use crate::persist::write_atomic;
+
+/// 日志中的一条操作，与 OrdIdMap 的写接口一一对应，重放时得到相同的结果（包括分配的 Id）
+#[derive(Debug, Serialize, Deserialize)]
+enum WalOp<K, E> {
+    Insert(E),
+    InsertWithId(K, E),
+    Remove(K),
+    Replace(K, E),
+}
+
+/// 写操作先记入预写日志的 OrdIdMap
+///
+/// 只读访问通过 Deref 直接使用内部的 OrdIdMap；写操作必须经由本类型以便记录。
+/// 写接口的外层 Result 为日志写入的错误，此时操作不会执行；内层为操作本身的结果
+pub struct WalOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    map: OrdIdMap<K, E, V>,
+    path: PathBuf,
+    log: BufWriter<File>,
+}
+
+impl<K, E, V> Deref for WalOrdIdMap<K, E, V>
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    type Target = OrdIdMap<K, E, V>;
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+fn apply<K, E, V>(map: &mut OrdIdMap<K, E, V>, op: WalOp<K, E>)
+where
+    K: Id,
+    E: Collexetable<V>,
+    V: FieldValue,
+{
+    match op {
+        WalOp::Insert(elem) => {
+            let _ = map.insert(elem);
+        }
+        WalOp::InsertWithId(id, elem) => {
+            let _ = map.insert_with_id(id, elem);
+        }
+        WalOp::Remove(id) => {
+            map.remove(id);
+        }
+        WalOp::Replace(id, elem) => {
+            let _ = map.replace(id, elem);
+        }
+    }
+}
+
+impl<K, E, V> WalOrdIdMap<K, E, V>
+where
+    K: Id + Serialize + DeserializeOwned,
+    E: Collexetable<V> + Serialize + DeserializeOwned,
+    V: FieldValue + Serialize + DeserializeOwned + fmt::Debug,
+{
+    /// 以 `map` 为初始状态新建日志，覆盖 `path` 处已有的文件
+    pub fn create(path: impl AsRef<Path>, map: OrdIdMap<K, E, V>) -> Result<Self, PersistError> {
+        let path = path.as_ref().to_path_buf();
+        let log = Self::write_base(&path, &map)?;
+        Ok(Self { map, path, log })
+    }
+    
+    /// 读取日志并重放，恢复到最后一条完整写入的操作之后的状态
+    ///
+    /// 最后一行若因崩溃而不完整会被丢弃
+    pub fn recover(path: impl AsRef<Path>) -> Result<Self, PersistError> {
+        let path = path.as_ref().to_path_buf();
+        let mut lines = BufReader::new(File::open(&path)?).lines();
+        let base = lines.next().transpose()?.unwrap_or_default();
+        let mut map = serde_json::from_str::<FullOrdIdMap<K, E, V>>(&base)?.into_inner();
+        
+        let mut lines = lines.peekable();
+        while let Some(line) = lines.next() {
+            let line = line?;
+            match serde_json::from_str::<WalOp<K, E>>(&line) {
+                Ok(op) => apply(&mut map, op),
+                Err(_) if lines.peek().is_none() => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        
+        // 重写为新快照，顺带去掉可能残留的不完整行
+        let log = Self::write_base(&path, &map)?;
+        Ok(Self { map, path, log })
+    }
+    
+    /// 把当前状态写成新快照，清空已记录的操作
+    pub fn checkpoint(&mut self) -> Result<(), PersistError> {
+        self.log = Self::write_base(&self.path, &self.map)?;
+        Ok(())
+    }
+    
+    pub fn into_inner(self) -> OrdIdMap<K, E, V> {
+        self.map
+    }
+    
+    pub fn insert(&mut self, elem: E) -> Result<Result<K, InsertFieldCollexError<E>>, PersistError> {
+        self.append(&WalOp::<K, &E>::Insert(&elem))?;
+        Ok(self.map.insert(elem))
+    }
+    
+    pub fn insert_with_id(&mut self, id: K, elem: E) -> Result<Result<Option<E>, InsertFieldCollexError<E>>, PersistError> {
+        self.append(&WalOp::<K, &E>::InsertWithId(id, &elem))?;
+        Ok(self.map.insert_with_id(id, elem))
+    }
+    
+    pub fn remove(&mut self, id: K) -> Result<Option<E>, PersistError> {
+        self.append(&WalOp::<K, &E>::Remove(id))?;
+        Ok(self.map.remove(id))
+    }
+    
+    pub fn replace(&mut self, id: K, new: E) -> Result<Result<E, ReplaceError<E>>, PersistError> {
+        self.append(&WalOp::<K, &E>::Replace(id, &new))?;
+        Ok(self.map.replace(id, new))
+    }
+    
+    /// 在元素的副本上执行闭包，以替换的方式写回（日志中记录修改后的完整元素）
+    ///
+    /// 修改后越界或冲突时原元素保持不变
+    pub fn modify<F, R>(&mut self, id: K, f: F) -> Result<Result<R, ModifyManyError>, PersistError>
+    where
+        E: Clone,
+        F: FnOnce(&mut E) -> R,
+    {
+        let Some(mut elem) = self.map.get_with_id(id).cloned() else {
+            return Ok(Err(ModifyManyError::CannotFind));
+        };
+        let r = f(&mut elem);
+        Ok(self.replace(id, elem)?
+            .map(|_| r)
+            .map_err(|err| match err {
+                ReplaceError::CannotFind(_) => ModifyManyError::CannotFind,
+                ReplaceError::OutOfSpan(_) => ModifyManyError::OutOfSpan,
+                ReplaceError::AlreadyExist(_) => ModifyManyError::AlreadyExist,
+            }))
+    }
+    
+    fn write_base(path: &Path, map: &OrdIdMap<K, E, V>) -> Result<BufWriter<File>, PersistError> {
+        write_atomic(path, |w| {
+            serde_json::to_writer(&mut *w, &as_full(map))?;
+            w.write_all(b"\n")?;
+            Ok(())
+        })?;
+        let file = OpenOptions::new().append(true).open(path)?;
+        Ok(BufWriter::new(file))
+    }
+    
+    fn append(&mut self, op: &WalOp<K, &E>) -> Result<(), PersistError> {
+        serde_json::to_writer(&mut self.log, op)?;
+        self.log.write_all(b"\n")?;
+        self.log.flush()?;
+        self.log.get_ref().sync_data()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use field_collex::collex::serialize::{default_span, default_unit};
+    use crate::{DefaultId, ModifyManyError, OrdIdMap};
+    use super::WalOrdIdMap;
+    use crate::test_util::TestO;
+    
+    #[test]
+    fn test_wal_recover() {
+        let path = std::env::temp_dir().join(format!("obj-alloc-wal-{}.log", std::process::id()));
+        let map: OrdIdMap<DefaultId, TestO, u32> = OrdIdMap::new(default_span(), default_unit()).unwrap();
+        let mut wal = WalOrdIdMap::create(&path, map).unwrap();
+        
+        let a = wal.insert(TestO(10)).unwrap().unwrap();
+        let b = wal.insert(TestO(20)).unwrap().unwrap();
+        wal.modify(a, |e| e.0 = 30).unwrap().unwrap();
+        assert_eq!(wal.modify(a, |e| e.0 = 20).unwrap(), Err(ModifyManyError::AlreadyExist));
+        wal.remove(b).unwrap();
+        let expected = wal.into_inner();
+        
+        // 模拟崩溃时写了一半的最后一行
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"Insert\":").unwrap();
+        drop(file);
+        
+        let mut recovered: WalOrdIdMap<DefaultId, TestO, u32> = WalOrdIdMap::recover(&path).unwrap();
+        assert_eq!(*recovered, expected);
+        assert_eq!(recovered.id_map.max_id(), b);
+        
+        // 恢复后可以继续记录
+        let c = recovered.insert(TestO(40)).unwrap().unwrap();
+        let again: WalOrdIdMap<DefaultId, TestO, u32> = WalOrdIdMap::recover(&path).unwrap();
+        assert_eq!(again.get_with_id(c), Some(&TestO(40)));
+        std::fs::remove_file(&path).unwrap();
+    }
+}